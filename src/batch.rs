@@ -0,0 +1,156 @@
+//!Parsing records in batches. A batch holds the positions of the fields of many records, so that a column can be
+//!processed across the records of a batch at once, as by vectorized code.
+
+use std::ops::Range;
+
+use crate::Parser;
+
+//The number of records memory is set aside for up front. A batch grows past it as records arrive.
+const PREALLOCATED: usize = 1024;
+
+///The records of a batch, as passed to the closure of ``Parser::parse_batches()``. The batch holds the position of
+///each field in the data, ``N`` per record. The memory of a batch grows with the first batch and is reused for every
+///following batch.
+pub struct RecordBatch<'a, const N: usize> {
+    data: &'a [u8],
+    first_index: usize,
+    //The fields of record i are at i * N
+    fields: Vec<Range<usize>>,
+    //The number of fields of each record
+    counts: Vec<usize>,
+}
+
+impl<'a, const N: usize> RecordBatch<'a, N> {
+    fn new(data: &'a [u8], capacity: usize) -> RecordBatch<'a, N> {
+        //The batch size is up to the caller and may be far more than the records of the data
+        let capacity = capacity.min(PREALLOCATED);
+
+        RecordBatch {
+            data,
+            first_index: 0,
+            fields: vec![0..0; capacity * N],
+            counts: Vec::with_capacity(capacity),
+        }
+    }
+
+    fn push(&mut self, fields: &[&[u8]]) {
+        let base = self.counts.len() * N;
+        let start = self.data.as_ptr() as usize;
+
+        if self.fields.len() < base + N {
+            self.fields.resize(base + N, 0..0);
+        }
+
+        for (slot, field) in self.fields[base..base + fields.len()].iter_mut().zip(fields) {
+            let offset = field.as_ptr() as usize - start;
+
+            *slot = offset..offset + field.len();
+        }
+
+        self.counts.push(fields.len());
+    }
+
+    ///Returns the number of records in the batch.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    ///Returns true if the batch has no records.
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    ///Returns the index of the first record of the batch in the data, as ``parse()`` would report it.
+    pub fn first_index(&self) -> usize {
+        self.first_index
+    }
+
+    ///Returns the data the batch was parsed from.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    ///Returns the number of fields of record ``record`` of the batch. It is never more than ``N``.
+    pub fn field_count(&self, record: usize) -> usize {
+        self.counts[record]
+    }
+
+    ///Returns the position in the data of field ``field`` of record ``record`` of the batch, or ``None`` if the record
+    ///does not have that many fields.
+    pub fn field_range(&self, record: usize, field: usize) -> Option<Range<usize>> {
+        if field < self.counts[record] {
+            Some(self.fields[record * N + field].clone())
+        } else {
+            None
+        }
+    }
+
+    ///Returns field ``field`` of record ``record`` of the batch, or ``None`` if the record does not have that many fields.
+    pub fn field(&self, record: usize, field: usize) -> Option<&'a [u8]> {
+        self.field_range(record, field).map(|range| &self.data[range])
+    }
+
+    ///Returns field ``field`` of every record of the batch, in order. A record that does not have that many fields
+    ///gives an empty field. Use ``field_count()`` to tell it apart from an empty field.
+    pub fn column(&self, field: usize) -> impl Iterator<Item = &'a [u8]> + '_ {
+        let data = self.data;
+
+        self.counts.iter().enumerate().map(move |(record, &count)| {
+            if field < count {
+                &data[self.fields[record * N + field].clone()]
+            } else {
+                &[]
+            }
+        })
+    }
+}
+
+impl Parser {
+    /// Parses CSV ``data`` like ``parse()``, but calls the ``consumer`` closure with batches of up to ``batch_size``
+    /// records instead of one record at a time. Only the last batch may have fewer records. The generic parameter
+    /// ``N`` is the maximum number of fields kept per record, as for ``parse()``.
+    ///
+    /// # Example
+    /// ```
+    /// let data = "name,amount\nPens,3\nInk,4\nPaper,5\n";
+    /// let mut parser = rcsv::Parser::new();
+    /// let mut total = 0;
+    ///
+    /// parser.parse_batches::<2>(data.as_bytes(), 2, |batch| {
+    ///     //Skip the header
+    ///     let skip = if batch.first_index() == 0 { 1 } else { 0 };
+    ///
+    ///     for amount in batch.column(1).skip(skip) {
+    ///         let mut n = 0;
+    ///
+    ///         assert!(rcsv::parse_number(amount, &mut n));
+    ///
+    ///         total += n;
+    ///     }
+    /// });
+    ///
+    /// assert!(total == 12);
+    /// ```
+    pub fn parse_batches<const N: usize>(&mut self, data: &[u8], batch_size: usize, mut consumer: impl FnMut(&RecordBatch<'_, N>)) {
+        let batch_size = batch_size.max(1);
+        let mut batch: RecordBatch<'_, N> = RecordBatch::new(data, batch_size);
+
+        self.parse::<N>(data, |index, fields| {
+            if batch.is_empty() {
+                batch.first_index = index;
+            }
+
+            batch.push(fields);
+
+            if batch.len() == batch_size {
+                consumer(&batch);
+
+                batch.counts.clear();
+            }
+        });
+
+        if !batch.is_empty() {
+            consumer(&batch);
+        }
+    }
+}
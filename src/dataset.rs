@@ -0,0 +1,298 @@
+//!Parsing of a data set that is split into multiple CSV files, such as a directory of ``part-0001.csv``, ``part-0002.csv``
+//!and so on. Every file starts with a header line.
+//!
+//!The files are memory mapped and the headers are reconciled by column name. The records of all files are
+//!then reported as one continuous stream with a global record index. The header lines are not reported.
+//!
+//! # Example
+//! ```no_run
+//! use rcsv::dataset::{Dataset, HeaderMode};
+//!
+//! let dataset = Dataset::from_dir("exports", "part-*.csv", HeaderMode::Align)
+//!     .unwrap()
+//!     .with_source_column("source_file");
+//!
+//! for name in dataset.columns() {
+//!     println!("Column: {}", String::from_utf8_lossy(name));
+//! }
+//!
+//! dataset.parse::<32>(|index, fields| {
+//!     //Fields are in the order of dataset.columns(). Columns missing from a file are empty.
+//! });
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::mmap::{FileMapper, MapError};
+use crate::{ParseStatus, Parser};
+
+///How the headers of the files are reconciled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderMode {
+    ///All files must have the same columns in the same order as the first file.
+    Strict,
+    ///The columns of the data set are all the column names found in the files, in the order they first appear.
+    ///Fields are rearranged by column name. Columns that are missing from a file are reported as empty fields.
+    Align,
+}
+
+///The reason a data set could not be opened.
+#[derive(Debug)]
+pub enum DatasetError {
+    ///The directory could not be listed.
+    Io(std::io::Error),
+    ///A file could not be memory mapped. The error has the path of the file.
+    Map(MapError),
+    ///In ``HeaderMode::Strict``, the header of a file does not match the header of the first file.
+    ///``column`` is the position of the first column that differs.
+    HeaderMismatch { path: PathBuf, column: usize },
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io(e) => write!(f, "Failed to list files. {}", e),
+            DatasetError::Map(e) => write!(f, "{}", e),
+            DatasetError::HeaderMismatch { path, column } => write!(f, "The header of {} differs from the first file at column {}.", path.display(), column),
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatasetError::Io(e) => Some(e),
+            DatasetError::Map(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DatasetError {
+    fn from(e: std::io::Error) -> Self {
+        DatasetError::Io(e)
+    }
+}
+
+impl From<MapError> for DatasetError {
+    fn from(e: MapError) -> Self {
+        DatasetError::Map(e)
+    }
+}
+
+struct DatasetFile {
+    path: PathBuf,
+    mapper: FileMapper,
+    //Offset of the first record after the header
+    body_start: usize,
+    //Position of each column of the file in the data set
+    columns: Vec<usize>,
+}
+
+///A data set made of multiple CSV files with headers.
+pub struct Dataset {
+    files: Vec<DatasetFile>,
+    columns: Vec<Vec<u8>>,
+    source_column: Option<Vec<u8>>,
+}
+
+///Parses the header line of ``data``. Returns the column names and the offset where the records start.
+///Returns ``None`` if there is no complete header line.
+fn read_header(data: &[u8]) -> Option<(Vec<&[u8]>, usize)> {
+    let mut parser = Parser::new();
+    let mut names = Vec::new();
+
+    parser.position = crate::encoding::utf8_bom_len(data);
+
+    loop {
+        let status = parser.next_field(data);
+
+        match status {
+            ParseStatus::HasMoreFields => names.push(parser.field(data)),
+            ParseStatus::EndRecord => {
+                names.push(parser.field(data));
+
+                return Some((names, parser.position));
+            }
+            ParseStatus::EndDocument => return None,
+        }
+    }
+}
+
+///Matches a file name against a pattern where ``*`` matches any sequence of characters and ``?`` matches any one character.
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    let mut p = 0;
+    let mut n = 0;
+    //Position after the last ``*`` and the name position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == b'*' {
+            p += 1;
+            backtrack = Some((p, n));
+        } else if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if let Some((bp, bn)) = backtrack {
+            //Let the last ``*`` match one more character
+            p = bp;
+            n = bn + 1;
+            backtrack = Some((bp, bn + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+impl Dataset {
+    ///Opens the data set made of the files at ``paths``. The records are reported in the order of the files.
+    pub fn new<P: AsRef<Path>>(paths: &[P], mode: HeaderMode) -> Result<Dataset, DatasetError> {
+        let mut files = Vec::with_capacity(paths.len());
+        let mut columns: Vec<Vec<u8>> = Vec::new();
+
+        for path in paths {
+            let path = path.as_ref();
+            let mapper = FileMapper::new(path)?;
+            let (names, body_start) = read_header(mapper.get_bytes()).unwrap_or((Vec::new(), mapper.size()));
+            let mut file_columns = Vec::with_capacity(names.len());
+            let first_header = columns.is_empty();
+
+            for (i, &column) in names.iter().enumerate() {
+                let position = match mode {
+                    HeaderMode::Strict => {
+                        if first_header {
+                            columns.push(column.to_vec());
+                        } else if i >= columns.len() || columns[i] != column {
+                            return Err(DatasetError::HeaderMismatch { path: path.to_path_buf(), column: i });
+                        }
+
+                        i
+                    }
+                    HeaderMode::Align => {
+                        //A repeated name in one file maps to the next column of that name
+                        let existing = (0..columns.len()).find(|&p| columns[p] == column && !file_columns.contains(&p));
+
+                        existing.unwrap_or_else(|| {
+                            columns.push(column.to_vec());
+
+                            columns.len() - 1
+                        })
+                    }
+                };
+
+                file_columns.push(position);
+            }
+
+            //A file without a header has no records and is not checked
+            if mode == HeaderMode::Strict && names.len() != columns.len() && !names.is_empty() {
+                return Err(DatasetError::HeaderMismatch { path: path.to_path_buf(), column: names.len() });
+            }
+
+            files.push(DatasetFile {
+                path: path.to_path_buf(),
+                mapper,
+                body_start,
+                columns: file_columns,
+            });
+        }
+
+        Ok(Dataset {
+            files,
+            columns,
+            source_column: None,
+        })
+    }
+
+    ///Opens the data set made of the files in ``dir`` whose names match ``pattern``. In the pattern ``*`` matches
+    ///any sequence of characters and ``?`` matches any one character. The files are ordered by name. Symbolic links
+    ///are followed, a link whose target is missing is an error. Directories are skipped.
+    pub fn from_dir<P: AsRef<Path>>(dir: P, pattern: &str, mode: HeaderMode) -> Result<Dataset, DatasetError> {
+        let mut paths = Vec::new();
+
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+
+            if glob_match(pattern.as_bytes(), entry.file_name().as_encoded_bytes()) && std::fs::metadata(entry.path())?.is_file() {
+                paths.push(entry.path());
+            }
+        }
+
+        paths.sort();
+
+        Dataset::new(&paths, mode)
+    }
+
+    ///Adds a column called ``name`` after all other columns. Its value is the path of the file the record comes from.
+    pub fn with_source_column(mut self, name: &str) -> Dataset {
+        self.source_column = Some(name.as_bytes().to_vec());
+
+        self
+    }
+
+    ///Returns the names of the columns of the data set in the order the fields are reported.
+    pub fn columns(&self) -> impl Iterator<Item = &[u8]> {
+        self.columns.iter().chain(self.source_column.iter()).map(|c| c.as_slice())
+    }
+
+    ///Returns the paths of the files in the order their records are reported.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|f| f.path.as_path())
+    }
+
+    ///Parses the records of all files. The closure is called for every record with the global index of the record
+    ///and the fields in the order of ``columns()``. The first record after the header of the first file has an index of 0.
+    ///
+    ///As in ``Parser::parse()``, the generic parameter ``N`` is the maximum number of fields reported. Fields of columns
+    ///beyond ``N`` are silently ignored, as are fields of a record beyond the number of columns in the header of its file.
+    pub fn parse<const N: usize>(&self, mut consumer: impl FnMut(usize, &[&[u8]])) {
+        let mut fields: [&[u8]; N] = [&[]; N];
+        let column_count = N.min(self.columns.len() + self.source_column.is_some() as usize);
+        let mut index: usize = 0;
+
+        for file in &self.files {
+            let data = file.mapper.get_bytes();
+            let source = file.path.as_os_str().as_encoded_bytes();
+            let mut parser = Parser::new();
+
+            parser.position = file.body_start;
+
+            'records: loop {
+                fields[0..column_count].fill(&[]);
+
+                if self.source_column.is_some() && self.columns.len() < N {
+                    fields[self.columns.len()] = source;
+                }
+
+                let mut column = 0;
+
+                loop {
+                    let status = parser.next_field(data);
+
+                    match status {
+                        ParseStatus::EndDocument => break 'records,
+                        ParseStatus::HasMoreFields | ParseStatus::EndRecord => {
+                            if let Some(&position) = file.columns.get(column) {
+                                if position < N {
+                                    fields[position] = parser.field(data);
+                                }
+                            }
+
+                            column += 1;
+
+                            if let ParseStatus::EndRecord = status {
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                consumer(index, &fields[0..column_count]);
+
+                index += 1;
+            }
+        }
+    }
+}
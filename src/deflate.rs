@@ -0,0 +1,694 @@
+//!A self-contained decoder of raw DEFLATE (RFC 1951) streams. This is the compression used by gzip and ZIP.
+//!
+//!The decoder reads from any ``std::io::Read`` and produces the decompressed data through its own ``Read``
+//!implementation. Memory use is constant: a 32 KB history window, a 16 KB input buffer and the Huffman tables.
+
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+const WINDOW_SIZE: usize = 32768;
+const WINDOW_MASK: usize = WINDOW_SIZE - 1;
+const INPUT_SIZE: usize = 16384;
+const MAX_BITS: usize = 15;
+//Codes up to this length are decoded by a single table lookup.
+const FAST_BITS: usize = 9;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097,
+    6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+//The order in which the code length code lengths are stored in a dynamic block header.
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+///The reason a DEFLATE stream could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InflateError {
+    ///The stream ended before the final block was complete.
+    UnexpectedEof,
+    ///A block header has the reserved block type 3.
+    InvalidBlockType,
+    ///The length of a stored block does not match its one's complement.
+    InvalidStoredLength,
+    ///The Huffman code lengths of a dynamic block do not describe a valid code.
+    InvalidCodeLengths,
+    ///The data contains a code that is not part of the Huffman code in use.
+    InvalidCode,
+    ///A back reference points before the start of the output.
+    InvalidDistance,
+}
+
+impl fmt::Display for InflateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            InflateError::UnexpectedEof => "Unexpected end of compressed data.",
+            InflateError::InvalidBlockType => "Invalid block type.",
+            InflateError::InvalidStoredLength => "Invalid stored block length.",
+            InflateError::InvalidCodeLengths => "Invalid Huffman code lengths.",
+            InflateError::InvalidCode => "Invalid Huffman code.",
+            InflateError::InvalidDistance => "Back reference distance is too far back.",
+        };
+
+        f.write_str(msg)
+    }
+}
+
+impl std::error::Error for InflateError {}
+
+fn corrupt(e: InflateError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+///Reads the input a byte at a time or a bit at a time. Bits are consumed least significant first as required by DEFLATE.
+struct BitReader<R> {
+    inner: R,
+    input: Box<[u8]>,
+    input_pos: usize,
+    input_len: usize,
+    inner_eof: bool,
+    bits: u64,
+    bit_count: usize,
+}
+
+impl<R: Read> BitReader<R> {
+    fn new(inner: R) -> BitReader<R> {
+        BitReader {
+            inner,
+            input: vec![0u8; INPUT_SIZE].into_boxed_slice(),
+            input_pos: 0,
+            input_len: 0,
+            inner_eof: false,
+            bits: 0,
+            bit_count: 0,
+        }
+    }
+
+    fn fill_input(&mut self) -> io::Result<()> {
+        while self.input_pos == self.input_len && !self.inner_eof {
+            match self.inner.read(&mut self.input) {
+                Ok(0) => self.inner_eof = true,
+                Ok(n) => {
+                    self.input_pos = 0;
+                    self.input_len = n;
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(())
+    }
+
+    ///Loads as many whole bytes into the bit buffer as will fit.
+    fn refill(&mut self) -> io::Result<()> {
+        while self.bit_count <= 56 {
+            if self.input_pos == self.input_len {
+                self.fill_input()?;
+
+                if self.input_pos == self.input_len {
+                    break;
+                }
+            }
+
+            self.bits |= (self.input[self.input_pos] as u64) << self.bit_count;
+            self.input_pos += 1;
+            self.bit_count += 8;
+        }
+
+        Ok(())
+    }
+
+    fn need(&mut self, n: usize) -> io::Result<()> {
+        if self.bit_count < n {
+            self.refill()?;
+
+            if self.bit_count < n {
+                return Err(corrupt(InflateError::UnexpectedEof));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.bits >>= n;
+        self.bit_count -= n;
+    }
+
+    fn take(&mut self, n: usize) -> io::Result<u32> {
+        self.need(n)?;
+
+        let v = (self.bits & ((1u64 << n) - 1)) as u32;
+
+        self.consume(n);
+
+        Ok(v)
+    }
+
+    ///Discards the bits up to the next byte boundary.
+    fn align(&mut self) {
+        let n = self.bit_count % 8;
+
+        self.consume(n);
+    }
+
+    ///Reads whole bytes after ``align()``. Returns 0 at the end of the input.
+    fn read_bytes(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+
+        while n < buf.len() && self.bit_count >= 8 {
+            buf[n] = self.bits as u8;
+            n += 1;
+
+            self.consume(8);
+        }
+
+        while n < buf.len() {
+            self.fill_input()?;
+
+            let available = self.input_len - self.input_pos;
+
+            if available == 0 {
+                break;
+            }
+
+            let len = available.min(buf.len() - n);
+
+            buf[n..n + len].copy_from_slice(&self.input[self.input_pos..self.input_pos + len]);
+            n += len;
+            self.input_pos += len;
+        }
+
+        Ok(n)
+    }
+
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    fn at_eof(&mut self) -> io::Result<bool> {
+        if self.bit_count >= 8 {
+            return Ok(false);
+        }
+
+        self.fill_input()?;
+
+        Ok(self.input_pos == self.input_len)
+    }
+}
+
+///A canonical Huffman code. Short codes are decoded with the ``fast`` lookup table. Longer codes are
+///decoded a bit at a time using the number of codes of each length.
+struct Huffman {
+    //Entry is (symbol << 4) | code length. Zero if the code is longer than FAST_BITS.
+    fast: [u16; 1 << FAST_BITS],
+    count: [u16; MAX_BITS + 1],
+    symbol: [u16; 288],
+}
+
+impl Huffman {
+    fn new() -> Huffman {
+        Huffman {
+            fast: [0; 1 << FAST_BITS],
+            count: [0; MAX_BITS + 1],
+            symbol: [0; 288],
+        }
+    }
+
+    ///Builds the code from the code length of each symbol. Incomplete codes are allowed but over subscribed ones are not.
+    fn build(&mut self, lengths: &[u8]) -> Result<(), InflateError> {
+        let mut offsets = [0u16; MAX_BITS + 2];
+
+        self.count = [0; MAX_BITS + 1];
+        self.fast = [0; 1 << FAST_BITS];
+
+        for &len in lengths {
+            self.count[len as usize] += 1;
+        }
+
+        let mut left: i32 = 1;
+
+        for len in 1..=MAX_BITS {
+            left <<= 1;
+            left -= self.count[len] as i32;
+
+            if left < 0 {
+                return Err(InflateError::InvalidCodeLengths);
+            }
+        }
+
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + self.count[len];
+        }
+
+        for (sym, &len) in lengths.iter().enumerate() {
+            if len != 0 {
+                self.symbol[offsets[len as usize] as usize] = sym as u16;
+                offsets[len as usize] += 1;
+            }
+        }
+
+        //Assign the canonical codes in order and fill the lookup table for the short ones.
+        //The codes are stored bit reversed in the stream.
+        let mut code: usize = 0;
+        let mut index: usize = 0;
+
+        for len in 1..=FAST_BITS {
+            for _ in 0..self.count[len] {
+                let sym = self.symbol[index] as usize;
+                let reversed = reverse_bits(code, len);
+                let entry = ((sym << 4) | len) as u16;
+                let mut fill = reversed;
+
+                while fill < (1 << FAST_BITS) {
+                    self.fast[fill] = entry;
+                    fill += 1 << len;
+                }
+
+                code += 1;
+                index += 1;
+            }
+
+            code <<= 1;
+        }
+
+        Ok(())
+    }
+
+    fn decode<R: Read>(&self, input: &mut BitReader<R>) -> io::Result<usize> {
+        if input.bit_count < MAX_BITS {
+            input.refill()?;
+        }
+
+        let entry = self.fast[(input.bits as usize) & ((1 << FAST_BITS) - 1)] as usize;
+        let len = entry & 15;
+
+        if entry != 0 && len <= input.bit_count {
+            input.consume(len);
+
+            return Ok(entry >> 4);
+        }
+
+        //Slow path for long codes
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for len in 1..=MAX_BITS {
+            code |= input.take(1)? as i32;
+
+            let count = self.count[len] as i32;
+
+            if code - first < count {
+                return Ok(self.symbol[(index + code - first) as usize] as usize);
+            }
+
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        Err(corrupt(InflateError::InvalidCode))
+    }
+}
+
+fn reverse_bits(code: usize, len: usize) -> usize {
+    let mut reversed = 0;
+
+    for i in 0..len {
+        reversed |= ((code >> i) & 1) << (len - 1 - i);
+    }
+
+    reversed
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    BlockHeader,
+    Stored(usize),
+    Codes,
+    Done,
+}
+
+///Decompresses a raw DEFLATE stream read from ``R``. Use ``Read::read()`` to get the decompressed data.
+///Corrupt data is reported as an ``io::Error`` of kind ``InvalidData`` that wraps an ``InflateError``.
+///
+/// # Example
+/// ```
+/// use std::io::Read;
+///
+/// //"abc" compressed using fixed Huffman codes
+/// let compressed: &[u8] = &[0x4b, 0x4c, 0x4a, 0x06, 0x00];
+/// let mut inflater = rcsv::deflate::Inflater::new(compressed);
+/// let mut out = Vec::new();
+///
+/// inflater.read_to_end(&mut out).unwrap();
+///
+/// assert!(out == b"abc");
+/// ```
+pub struct Inflater<R> {
+    input: BitReader<R>,
+    window: Box<[u8]>,
+    window_pos: usize,
+    total_out: u64,
+    state: State,
+    last_block: bool,
+    copy_len: usize,
+    copy_dist: usize,
+    lit: Box<Huffman>,
+    dist: Box<Huffman>,
+}
+
+impl<R: Read> Inflater<R> {
+    ///Creates a decoder of the DEFLATE stream read from ``inner``.
+    pub fn new(inner: R) -> Inflater<R> {
+        Inflater {
+            input: BitReader::new(inner),
+            window: vec![0u8; WINDOW_SIZE].into_boxed_slice(),
+            window_pos: 0,
+            total_out: 0,
+            state: State::BlockHeader,
+            last_block: false,
+            copy_len: 0,
+            copy_dist: 0,
+            lit: Box::new(Huffman::new()),
+            dist: Box::new(Huffman::new()),
+        }
+    }
+
+    ///Returns true when the final block of the stream has been decoded.
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done && self.copy_len == 0
+    }
+
+    ///Returns the number of decompressed bytes produced so far.
+    pub fn total_out(&self) -> u64 {
+        self.total_out
+    }
+
+    ///Prepares the decoder for another DEFLATE stream that follows in the same input.
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    pub(crate) fn reset(&mut self) {
+        self.window_pos = 0;
+        self.total_out = 0;
+        self.state = State::BlockHeader;
+        self.last_block = false;
+        self.copy_len = 0;
+        self.copy_dist = 0;
+    }
+
+    ///Reads bytes that follow the end of the DEFLATE stream, such as a gzip trailer.
+    ///Returns fewer bytes than requested only at the end of the input.
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    pub(crate) fn read_raw(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.input.align();
+
+        let mut n = 0;
+
+        while n < buf.len() {
+            let len = self.input.read_bytes(&mut buf[n..])?;
+
+            if len == 0 {
+                break;
+            }
+
+            n += len;
+        }
+
+        Ok(n)
+    }
+
+    ///Returns true if there is no more input after the end of the DEFLATE stream.
+    #[cfg_attr(not(feature = "gzip"), allow(dead_code))]
+    pub(crate) fn raw_eof(&mut self) -> io::Result<bool> {
+        self.input.align();
+        self.input.at_eof()
+    }
+
+    #[inline]
+    fn put(&mut self, out: &mut [u8], n: &mut usize, b: u8) {
+        out[*n] = b;
+        *n += 1;
+
+        self.window[self.window_pos & WINDOW_MASK] = b;
+        self.window_pos = self.window_pos.wrapping_add(1);
+        self.total_out += 1;
+    }
+
+    fn block_header(&mut self) -> io::Result<()> {
+        if self.last_block {
+            self.state = State::Done;
+
+            return Ok(());
+        }
+
+        self.last_block = self.input.take(1)? == 1;
+
+        match self.input.take(2)? {
+            0 => {
+                let mut header = [0u8; 4];
+
+                self.input.align();
+
+                if self.input.read_bytes(&mut header)? < 4 {
+                    return Err(corrupt(InflateError::UnexpectedEof));
+                }
+
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+
+                if len != !nlen {
+                    return Err(corrupt(InflateError::InvalidStoredLength));
+                }
+
+                self.state = State::Stored(len as usize);
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+
+                lengths[0..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..288].fill(8);
+
+                self.lit.build(&lengths).map_err(corrupt)?;
+                self.dist.build(&[5u8; 30]).map_err(corrupt)?;
+
+                self.state = State::Codes;
+            }
+            2 => {
+                self.dynamic_tables()?;
+
+                self.state = State::Codes;
+            }
+            _ => return Err(corrupt(InflateError::InvalidBlockType)),
+        }
+
+        Ok(())
+    }
+
+    fn dynamic_tables(&mut self) -> io::Result<()> {
+        let hlit = self.input.take(5)? as usize + 257;
+        let hdist = self.input.take(5)? as usize + 1;
+        let hclen = self.input.take(4)? as usize + 4;
+
+        if hlit > 286 || hdist > 30 {
+            return Err(corrupt(InflateError::InvalidCodeLengths));
+        }
+
+        let mut clen_lengths = [0u8; 19];
+
+        for &i in CLEN_ORDER.iter().take(hclen) {
+            clen_lengths[i] = self.input.take(3)? as u8;
+        }
+
+        let mut clen = Huffman::new();
+
+        clen.build(&clen_lengths).map_err(corrupt)?;
+
+        let mut lengths = [0u8; 286 + 30];
+        let mut i = 0;
+
+        while i < hlit + hdist {
+            let sym = clen.decode(&mut self.input)?;
+
+            if sym < 16 {
+                lengths[i] = sym as u8;
+                i += 1;
+
+                continue;
+            }
+
+            let (value, repeat) = match sym {
+                16 => {
+                    if i == 0 {
+                        return Err(corrupt(InflateError::InvalidCodeLengths));
+                    }
+
+                    (lengths[i - 1], 3 + self.input.take(2)? as usize)
+                }
+                17 => (0, 3 + self.input.take(3)? as usize),
+                _ => (0, 11 + self.input.take(7)? as usize),
+            };
+
+            if i + repeat > hlit + hdist {
+                return Err(corrupt(InflateError::InvalidCodeLengths));
+            }
+
+            lengths[i..i + repeat].fill(value);
+            i += repeat;
+        }
+
+        //The end of block code must be present
+        if lengths[256] == 0 {
+            return Err(corrupt(InflateError::InvalidCodeLengths));
+        }
+
+        self.lit.build(&lengths[0..hlit]).map_err(corrupt)?;
+        self.dist.build(&lengths[hlit..hlit + hdist]).map_err(corrupt)?;
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Inflater<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+
+        while n < buf.len() {
+            if self.copy_len > 0 {
+                while self.copy_len > 0 && n < buf.len() {
+                    let b = self.window[self.window_pos.wrapping_sub(self.copy_dist) & WINDOW_MASK];
+
+                    self.put(buf, &mut n, b);
+                    self.copy_len -= 1;
+                }
+
+                continue;
+            }
+
+            match self.state {
+                State::Done => break,
+                State::BlockHeader => self.block_header()?,
+                State::Stored(remaining) => {
+                    if remaining == 0 {
+                        self.state = State::BlockHeader;
+
+                        continue;
+                    }
+
+                    let len = remaining.min(buf.len() - n);
+                    let got = self.input.read_bytes(&mut buf[n..n + len])?;
+
+                    if got == 0 {
+                        return Err(corrupt(InflateError::UnexpectedEof));
+                    }
+
+                    for &b in &buf[n..n + got] {
+                        self.window[self.window_pos & WINDOW_MASK] = b;
+                        self.window_pos = self.window_pos.wrapping_add(1);
+                    }
+
+                    n += got;
+                    self.total_out += got as u64;
+                    self.state = State::Stored(remaining - got);
+                }
+                State::Codes => {
+                    let sym = self.lit.decode(&mut self.input)?;
+
+                    if sym < 256 {
+                        self.put(buf, &mut n, sym as u8);
+
+                        continue;
+                    }
+
+                    if sym == 256 {
+                        self.state = State::BlockHeader;
+
+                        continue;
+                    }
+
+                    let sym = sym - 257;
+
+                    if sym >= LENGTH_BASE.len() {
+                        return Err(corrupt(InflateError::InvalidCode));
+                    }
+
+                    let len = LENGTH_BASE[sym] as usize + self.input.take(LENGTH_EXTRA[sym] as usize)? as usize;
+                    let dsym = self.dist.decode(&mut self.input)?;
+
+                    if dsym >= DIST_BASE.len() {
+                        return Err(corrupt(InflateError::InvalidCode));
+                    }
+
+                    let dist = DIST_BASE[dsym] as usize + self.input.take(DIST_EXTRA[dsym] as usize)? as usize;
+
+                    if dist as u64 > self.total_out {
+                        return Err(corrupt(InflateError::InvalidDistance));
+                    }
+
+                    self.copy_len = len;
+                    self.copy_dist = dist;
+                }
+            }
+        }
+
+        Ok(n)
+    }
+}
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut c = i as u32;
+        let mut k = 0;
+
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+
+        table[i] = c;
+        i += 1;
+    }
+
+    table
+}
+
+static CRC32_TABLE: [u32; 256] = crc32_table();
+
+///Computes the CRC-32 checksum used by gzip and ZIP to verify the decompressed data.
+#[derive(Clone, Copy, Default)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32 { crc: 0 }
+    }
+
+    ///Adds ``data`` to the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        let mut c = !self.crc;
+
+        for &b in data {
+            c = CRC32_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8);
+        }
+
+        self.crc = !c;
+    }
+
+    ///Returns the checksum of the data added so far.
+    pub fn value(&self) -> u32 {
+        self.crc
+    }
+}
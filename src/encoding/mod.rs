@@ -0,0 +1,40 @@
+//!Support for CSV data that is not encoded in UTF-8.
+//!
+//!UTF-16 data is transcoded to UTF-8 by ``Utf16Decoder`` a piece at a time before it is parsed. Data that
+//!starts with a UTF-16 byte order mark is detected automatically by ``Parser::parse_reader()`` and ``Parser::parse_auto()``.
+//!
+//!Data in a legacy single byte ``Encoding``, such as Windows-1252, is parsed as is. The fields are transcoded
+//!to UTF-8 on demand into a buffer supplied by the caller.
+
+mod single_byte;
+mod utf16;
+
+pub use single_byte::{BufferTooSmall, Encoding};
+pub use utf16::{Endian, Utf16Decoder, Utf16Error};
+
+///The UTF-8 byte order mark. The parser skips it at the start of the data.
+pub const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+///The UTF-16 little endian byte order mark.
+pub const UTF16LE_BOM: [u8; 2] = [0xff, 0xfe];
+///The UTF-16 big endian byte order mark.
+pub const UTF16BE_BOM: [u8; 2] = [0xfe, 0xff];
+
+///Returns the length of the UTF-8 byte order mark at the start of ``data``. Zero if there is none.
+pub(crate) fn utf8_bom_len(data: &[u8]) -> usize {
+    if data.starts_with(&UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    }
+}
+
+///Returns the byte order of ``data`` if it starts with a UTF-16 byte order mark.
+pub(crate) fn utf16_bom(data: &[u8]) -> Option<Endian> {
+    if data.starts_with(&UTF16LE_BOM) {
+        Some(Endian::Little)
+    } else if data.starts_with(&UTF16BE_BOM) {
+        Some(Endian::Big)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,112 @@
+use std::fmt;
+
+//Characters of Windows-1252 for the bytes 0x80 to 0x9f. Bytes that are not defined map to the C1 control
+//character of the same value, as browsers do.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+///A single byte encoding used by legacy Western European systems.
+///
+///The tokenizer works on raw bytes and all of these encodings agree with ASCII on the characters that matter
+///to it (comma, double quote, CR and LF). So the data is parsed as usual and only the fields that are needed
+///are transcoded to UTF-8 with ``decode()``.
+///
+/// # Example
+/// ```
+/// use rcsv::encoding::Encoding;
+///
+/// //"Müller,5 €" in Windows-1252
+/// let data = b"M\xfcller,5 \x80\r\n";
+/// let mut out = [0u8; 64];
+/// let mut parser = rcsv::Parser::new();
+///
+/// parser.parse::<2>(data, |_, fields| {
+///     assert!(Encoding::Windows1252.decode(fields[0], &mut out).unwrap() == "Müller");
+///     assert!(Encoding::Windows1252.decode(fields[1], &mut out).unwrap() == "5 €");
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    ///Windows-1252, the default code page of Western European Windows systems.
+    Windows1252,
+    ///ISO-8859-1, also known as Latin-1.
+    Latin1,
+    ///ISO-8859-15, also known as Latin-9. It is Latin-1 with the euro sign and a few other letters.
+    Latin9,
+}
+
+///The output buffer passed to ``Encoding::decode()`` is too small. ``required`` is the size needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferTooSmall {
+    pub required: usize,
+}
+
+impl fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The output buffer is too small. {} bytes are required.", self.required)
+    }
+}
+
+impl std::error::Error for BufferTooSmall {}
+
+impl Encoding {
+    ///Returns the encoding with the name ``label``, such as ``"windows-1252"``, ``"cp1252"``, ``"iso-8859-1"``,
+    ///``"latin1"`` or ``"iso-8859-15"``. The comparison is case insensitive.
+    pub fn from_label(label: &str) -> Option<Encoding> {
+        match label.trim().to_ascii_lowercase().as_str() {
+            "windows-1252" | "cp1252" | "x-cp1252" => Some(Encoding::Windows1252),
+            "iso-8859-1" | "iso8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1" => Some(Encoding::Latin1),
+            "iso-8859-15" | "iso8859-15" | "iso_8859-15" | "latin9" | "latin-9" | "l9" => Some(Encoding::Latin9),
+            _ => None,
+        }
+    }
+
+    ///Returns the character for the byte ``b``.
+    pub fn decode_byte(&self, b: u8) -> char {
+        match (self, b) {
+            (_, 0..=0x7f) => b as char,
+            (Encoding::Windows1252, 0x80..=0x9f) => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+            (Encoding::Latin9, 0xa4) => '\u{20ac}',
+            (Encoding::Latin9, 0xa6) => '\u{160}',
+            (Encoding::Latin9, 0xa8) => '\u{161}',
+            (Encoding::Latin9, 0xb4) => '\u{17d}',
+            (Encoding::Latin9, 0xb8) => '\u{17e}',
+            (Encoding::Latin9, 0xbc) => '\u{152}',
+            (Encoding::Latin9, 0xbd) => '\u{153}',
+            (Encoding::Latin9, 0xbe) => '\u{178}',
+            _ => b as char,
+        }
+    }
+
+    ///Returns the length of ``field`` once transcoded to UTF-8.
+    pub fn decoded_len(&self, field: &[u8]) -> usize {
+        field.iter().map(|&b| self.decode_byte(b).len_utf8()).sum()
+    }
+
+    ///Transcodes ``field`` to UTF-8. Nothing is allocated. If the field is pure ASCII, it is returned as is.
+    ///Otherwise it is transcoded into ``out``, which needs room for at most three times the length of the field.
+    ///Every byte is a valid character in these encodings, so the only possible error is a short ``out``.
+    pub fn decode<'a>(&self, field: &'a [u8], out: &'a mut [u8]) -> Result<&'a str, BufferTooSmall> {
+        if field.is_ascii() {
+            return Ok(std::str::from_utf8(field).expect("ASCII is valid UTF-8"));
+        }
+
+        let mut n = 0;
+
+        for &b in field {
+            let c = self.decode_byte(b);
+
+            if n + c.len_utf8() > out.len() {
+                return Err(BufferTooSmall { required: self.decoded_len(field) });
+            }
+
+            n += c.encode_utf8(&mut out[n..]).len();
+        }
+
+        Ok(std::str::from_utf8(&out[0..n]).expect("Encoded characters are valid UTF-8"))
+    }
+}
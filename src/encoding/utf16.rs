@@ -0,0 +1,197 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+const INPUT_SIZE: usize = 8192;
+
+///The byte order of UTF-16 data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+///The reason UTF-16 data could not be transcoded. ``offset`` is the position of the offending code unit in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf16Error {
+    ///A high surrogate is not followed by a low surrogate, or a low surrogate is not preceded by a high surrogate.
+    UnpairedSurrogate { offset: u64 },
+    ///The data ends in the middle of a code unit.
+    OddLength { offset: u64 },
+}
+
+impl fmt::Display for Utf16Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Utf16Error::UnpairedSurrogate { offset } => write!(f, "Unpaired surrogate at offset {}.", offset),
+            Utf16Error::OddLength { offset } => write!(f, "Incomplete code unit at offset {}.", offset),
+        }
+    }
+}
+
+impl std::error::Error for Utf16Error {}
+
+fn invalid(e: Utf16Error) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+///Transcodes UTF-16 data read from ``R`` to UTF-8. Use ``Read::read()`` to get the UTF-8 data. The input is read
+///into a small internal buffer, so memory use is constant.
+///
+///Invalid data is reported as an ``io::Error`` of kind ``InvalidData`` that wraps a ``Utf16Error``. The parsers
+///convert it to ``Error::Utf16``. A byte order mark is not expected. If present it is transcoded like any other character.
+///
+/// # Example
+/// ```
+/// use rcsv::encoding::{Endian, Utf16Decoder};
+///
+/// let data: Vec<u8> = "aa,é\r\n".encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+/// let mut buffer = [0u8; 1024];
+/// let mut parser = rcsv::Parser::new();
+///
+/// parser.parse_reader::<2, _>(Utf16Decoder::new(data.as_slice(), Endian::Little), &mut buffer, |_, fields| {
+///     assert!(fields[1] == "é".as_bytes());
+/// }).unwrap();
+/// ```
+pub struct Utf16Decoder<R> {
+    inner: R,
+    endian: Endian,
+    input: Box<[u8]>,
+    input_pos: usize,
+    input_len: usize,
+    inner_eof: bool,
+    //Offset in the input of input[input_pos]
+    offset: u64,
+    //UTF-8 bytes of a character that did not fit in the output
+    pending: [u8; 4],
+    pending_pos: usize,
+    pending_len: usize,
+}
+
+impl<R: Read> Utf16Decoder<R> {
+    ///Creates a decoder of the UTF-16 data read from ``inner`` with the byte order ``endian``.
+    pub fn new(inner: R, endian: Endian) -> Utf16Decoder<R> {
+        Utf16Decoder {
+            inner,
+            endian,
+            input: vec![0u8; INPUT_SIZE].into_boxed_slice(),
+            input_pos: 0,
+            input_len: 0,
+            inner_eof: false,
+            offset: 0,
+            pending: [0; 4],
+            pending_pos: 0,
+            pending_len: 0,
+        }
+    }
+
+    ///Sets the offset of the first byte read from the inner reader. It is used in errors. This is useful when
+    ///a byte order mark has already been consumed.
+    pub(crate) fn with_offset(mut self, offset: u64) -> Utf16Decoder<R> {
+        self.offset = offset;
+
+        self
+    }
+
+    ///Makes sure at least ``n`` bytes are available in the input buffer unless the end of the input is reached.
+    fn fill(&mut self, n: usize) -> io::Result<usize> {
+        if self.input_len - self.input_pos < n {
+            self.input.copy_within(self.input_pos..self.input_len, 0);
+            self.input_len -= self.input_pos;
+            self.input_pos = 0;
+
+            while self.input_len < n && !self.inner_eof {
+                match self.inner.read(&mut self.input[self.input_len..]) {
+                    Ok(0) => self.inner_eof = true,
+                    Ok(len) => self.input_len += len,
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Ok(self.input_len - self.input_pos)
+    }
+
+    fn unit(&self, pos: usize) -> u16 {
+        let b = [self.input[pos], self.input[pos + 1]];
+
+        match self.endian {
+            Endian::Little => u16::from_le_bytes(b),
+            Endian::Big => u16::from_be_bytes(b),
+        }
+    }
+
+    ///Decodes the next character. Returns ``None`` at the end of the input.
+    fn next_char(&mut self) -> io::Result<Option<char>> {
+        let available = self.fill(2)?;
+
+        if available == 0 {
+            return Ok(None);
+        }
+
+        if available == 1 {
+            return Err(invalid(Utf16Error::OddLength { offset: self.offset }));
+        }
+
+        let unit = self.unit(self.input_pos);
+
+        if (0xdc00..0xe000).contains(&unit) {
+            return Err(invalid(Utf16Error::UnpairedSurrogate { offset: self.offset }));
+        }
+
+        if !(0xd800..0xdc00).contains(&unit) {
+            self.input_pos += 2;
+            self.offset += 2;
+
+            //Not a surrogate, hence always a valid char
+            return Ok(char::from_u32(unit as u32));
+        }
+
+        if self.fill(4)? < 4 || !(0xdc00..0xe000).contains(&self.unit(self.input_pos + 2)) {
+            return Err(invalid(Utf16Error::UnpairedSurrogate { offset: self.offset }));
+        }
+
+        let low = self.unit(self.input_pos + 2);
+        let c = 0x10000 + (((unit as u32) - 0xd800) << 10) + ((low as u32) - 0xdc00);
+
+        self.input_pos += 4;
+        self.offset += 4;
+
+        Ok(char::from_u32(c))
+    }
+}
+
+impl<R: Read> Read for Utf16Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+
+        while n < buf.len() {
+            if self.pending_pos < self.pending_len {
+                let len = (self.pending_len - self.pending_pos).min(buf.len() - n);
+
+                buf[n..n + len].copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + len]);
+                n += len;
+                self.pending_pos += len;
+
+                continue;
+            }
+
+            let c = match self.next_char() {
+                Ok(Some(c)) => c,
+                Ok(None) => break,
+                //Report the data transcoded so far. The error is returned again by the next call.
+                Err(e) if n > 0 && e.kind() == ErrorKind::InvalidData => break,
+                Err(e) => return Err(e),
+            };
+
+            if c.len_utf8() <= buf.len() - n {
+                n += c.encode_utf8(&mut buf[n..]).len();
+            } else {
+                self.pending_len = c.encode_utf8(&mut self.pending).len();
+                self.pending_pos = 0;
+            }
+        }
+
+        Ok(n)
+    }
+}
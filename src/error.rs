@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io;
+
+use crate::encoding::Utf16Error;
+use crate::mmap::MapError;
+#[cfg(feature = "gzip")]
+use crate::gzip::GzipError;
+#[cfg(feature = "zip")]
+use crate::zip::ZipError;
+
+///The error returned by the parsers that read their input from a stream. Parsing in-memory data with
+///``Parser::parse()`` never fails.
+#[derive(Debug)]
+pub enum Error {
+    ///Reading the input failed.
+    Io(io::Error),
+    ///A record did not fit in the buffer supplied to the parser. ``offset`` is the position in the
+    ///(decompressed) input where the record starts.
+    RecordTooLong { offset: u64 },
+    ///The input is UTF-16 encoded and is invalid.
+    Utf16(Utf16Error),
+    ///A part of the input file could not be memory mapped.
+    Map(MapError),
+    ///The input is gzip compressed and is corrupt.
+    #[cfg(feature = "gzip")]
+    Gzip(GzipError),
+    ///The input is a member of a ZIP archive and the archive or member is corrupt.
+    #[cfg(feature = "zip")]
+    Zip(ZipError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "Failed to read input. {}", e),
+            Error::RecordTooLong { offset } => write!(f, "The record at offset {} does not fit in the parse buffer.", offset),
+            Error::Utf16(e) => write!(f, "Invalid UTF-16 data. {}", e),
+            Error::Map(e) => write!(f, "{}", e),
+            #[cfg(feature = "gzip")]
+            Error::Gzip(e) => write!(f, "Invalid gzip data. {}", e),
+            #[cfg(feature = "zip")]
+            Error::Zip(e) => write!(f, "Invalid ZIP archive. {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Utf16(e) => Some(e),
+            Error::Map(e) => Some(e),
+            #[cfg(feature = "gzip")]
+            Error::Gzip(e) => Some(e),
+            #[cfg(feature = "zip")]
+            Error::Zip(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    ///Decoders report corrupt data as ``io::Error`` since they implement ``std::io::Read``.
+    ///Such errors are unwrapped here so that they surface as parse errors.
+    fn from(e: io::Error) -> Self {
+        let e = match take_inner::<Utf16Error>(e) {
+            Ok(inner) => return Error::Utf16(inner),
+            Err(e) => e,
+        };
+
+        #[cfg(feature = "gzip")]
+        let e = match take_inner::<GzipError>(e) {
+            Ok(inner) => return Error::Gzip(inner),
+            Err(e) => e,
+        };
+
+        #[cfg(feature = "zip")]
+        let e = match take_inner::<ZipError>(e) {
+            Ok(inner) => return Error::Zip(inner),
+            Err(e) => e,
+        };
+
+        Error::Io(e)
+    }
+}
+
+impl From<MapError> for Error {
+    fn from(e: MapError) -> Self {
+        Error::Map(e)
+    }
+}
+
+#[cfg(feature = "zip")]
+impl From<ZipError> for Error {
+    fn from(e: ZipError) -> Self {
+        Error::Zip(e)
+    }
+}
+
+///Extracts the error of type ``T`` wrapped by ``e``. If ``e`` wraps something else it is returned as is.
+pub(crate) fn take_inner<T: std::error::Error + Send + Sync + 'static>(e: io::Error) -> Result<T, io::Error> {
+    if !e.get_ref().is_some_and(|inner| inner.is::<T>()) {
+        return Err(e);
+    }
+
+    match e.into_inner().map(|inner| inner.downcast::<T>()) {
+        Some(Ok(inner)) => Ok(*inner),
+        _ => unreachable!(),
+    }
+}
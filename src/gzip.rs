@@ -0,0 +1,253 @@
+//!Decompression of gzip (RFC 1952) data. This is available when the ``gzip`` feature is enabled.
+//!
+//!There is usually no need to use this module directly. ``Parser::parse_reader()`` and ``Parser::parse_auto()``
+//!detect gzip data by its magic bytes and decompress it on the fly. Compressed data is never fully decompressed
+//!in memory.
+//!
+//! # Example
+//! ```
+//! let mapper = rcsv::mmap::FileMapper::new(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv.gz")).unwrap();
+//! let mut buffer = [0u8; 64 * 1024];
+//! let mut parser = rcsv::Parser::new();
+//!
+//! parser.parse_auto::<3>(mapper.get_bytes(), &mut buffer, |index, fields| {
+//!     if index == 0 {
+//!         assert!(fields[0] == "aa".as_bytes());
+//!     }
+//! }).unwrap();
+//! ```
+
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+use crate::deflate::{Crc32, InflateError, Inflater};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const METHOD_DEFLATE: u8 = 8;
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+const FLAG_RESERVED: u8 = 0xe0;
+
+///The reason gzip data could not be decompressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GzipError {
+    ///The data does not start with the gzip magic bytes.
+    NotGzip,
+    ///The compression method is not DEFLATE.
+    UnsupportedMethod(u8),
+    ///The header has reserved flags set or is truncated.
+    InvalidHeader,
+    ///The checksum of the header does not match.
+    HeaderCrcMismatch,
+    ///The compressed data is corrupt.
+    Deflate(InflateError),
+    ///The CRC-32 of the decompressed data does not match the one in the trailer.
+    CrcMismatch { expected: u32, actual: u32 },
+    ///The size of the decompressed data (modulo 2^32) does not match the one in the trailer.
+    LengthMismatch { expected: u32, actual: u32 },
+    ///The data ended in the trailer.
+    UnexpectedEof,
+}
+
+impl fmt::Display for GzipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GzipError::NotGzip => write!(f, "Not in gzip format."),
+            GzipError::UnsupportedMethod(m) => write!(f, "Unsupported compression method {}.", m),
+            GzipError::InvalidHeader => write!(f, "Invalid header."),
+            GzipError::HeaderCrcMismatch => write!(f, "Header checksum mismatch."),
+            GzipError::Deflate(e) => write!(f, "{}", e),
+            GzipError::CrcMismatch { expected, actual } => write!(f, "CRC mismatch. Expected {:08x} but got {:08x}.", expected, actual),
+            GzipError::LengthMismatch { expected, actual } => write!(f, "Length mismatch. Expected {} but got {}.", expected, actual),
+            GzipError::UnexpectedEof => write!(f, "Unexpected end of data."),
+        }
+    }
+}
+
+impl std::error::Error for GzipError {}
+
+fn corrupt(e: GzipError) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, e)
+}
+
+///Returns true if ``data`` starts with the gzip magic bytes.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Header,
+    Body,
+    Done,
+}
+
+///Decompresses gzip data read from ``R``. Use ``Read::read()`` to get the decompressed data.
+///Data with multiple gzip members, as produced by concatenating ``.gz`` files, is decompressed as a whole.
+///
+///The CRC-32 and length in the trailer of each member are verified. Corrupt data is reported as an ``io::Error``
+///of kind ``InvalidData`` that wraps a ``GzipError``. The parsers convert it to ``Error::Gzip``.
+pub struct GzDecoder<R> {
+    inflater: Inflater<R>,
+    state: State,
+    crc: Crc32,
+}
+
+impl<R: Read> GzDecoder<R> {
+    ///Creates a decoder of the gzip data read from ``inner``.
+    pub fn new(inner: R) -> GzDecoder<R> {
+        GzDecoder {
+            inflater: Inflater::new(inner),
+            state: State::Header,
+            crc: Crc32::new(),
+        }
+    }
+
+    fn read_exact_raw(&mut self, buf: &mut [u8], header_crc: &mut Crc32) -> io::Result<()> {
+        if self.inflater.read_raw(buf)? < buf.len() {
+            return Err(corrupt(GzipError::InvalidHeader));
+        }
+
+        header_crc.update(buf);
+
+        Ok(())
+    }
+
+    ///Skips a zero terminated string in the header.
+    fn skip_string(&mut self, header_crc: &mut Crc32) -> io::Result<()> {
+        let mut b = [0xffu8; 1];
+
+        while b[0] != 0 {
+            self.read_exact_raw(&mut b, header_crc)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_header(&mut self) -> io::Result<()> {
+        let mut header_crc = Crc32::new();
+        let mut fixed = [0u8; 10];
+
+        if self.inflater.read_raw(&mut fixed[0..2])? < 2 || fixed[0..2] != MAGIC {
+            return Err(corrupt(GzipError::NotGzip));
+        }
+
+        header_crc.update(&fixed[0..2]);
+
+        self.read_exact_raw(&mut fixed[2..10], &mut header_crc)?;
+
+        if fixed[2] != METHOD_DEFLATE {
+            return Err(corrupt(GzipError::UnsupportedMethod(fixed[2])));
+        }
+
+        let flags = fixed[3];
+
+        if flags & FLAG_RESERVED != 0 {
+            return Err(corrupt(GzipError::InvalidHeader));
+        }
+
+        if flags & FLAG_EXTRA != 0 {
+            let mut len = [0u8; 2];
+
+            self.read_exact_raw(&mut len, &mut header_crc)?;
+
+            let mut remaining = u16::from_le_bytes(len) as usize;
+            let mut skip = [0u8; 64];
+
+            while remaining > 0 {
+                let n = remaining.min(skip.len());
+
+                self.read_exact_raw(&mut skip[0..n], &mut header_crc)?;
+                remaining -= n;
+            }
+        }
+
+        if flags & FLAG_NAME != 0 {
+            self.skip_string(&mut header_crc)?;
+        }
+
+        if flags & FLAG_COMMENT != 0 {
+            self.skip_string(&mut header_crc)?;
+        }
+
+        if flags & FLAG_HCRC != 0 {
+            let mut crc16 = [0u8; 2];
+            let expected = header_crc.value() as u16;
+
+            self.read_exact_raw(&mut crc16, &mut header_crc)?;
+
+            if u16::from_le_bytes(crc16) != expected {
+                return Err(corrupt(GzipError::HeaderCrcMismatch));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_trailer(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; 8];
+
+        if self.inflater.read_raw(&mut trailer)? < trailer.len() {
+            return Err(corrupt(GzipError::UnexpectedEof));
+        }
+
+        let expected_crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let expected_len = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+
+        if expected_crc != self.crc.value() {
+            return Err(corrupt(GzipError::CrcMismatch { expected: expected_crc, actual: self.crc.value() }));
+        }
+
+        let actual_len = self.inflater.total_out() as u32;
+
+        if expected_len != actual_len {
+            return Err(corrupt(GzipError::LengthMismatch { expected: expected_len, actual: actual_len }));
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            match self.state {
+                State::Done => return Ok(0),
+                State::Header => {
+                    self.read_header()?;
+                    self.inflater.reset();
+                    self.crc = Crc32::new();
+                    self.state = State::Body;
+                }
+                State::Body => {
+                    let n = match self.inflater.read(buf) {
+                        Ok(n) => n,
+                        Err(e) => {
+                            return Err(match crate::error::take_inner::<InflateError>(e) {
+                                Ok(inner) => corrupt(GzipError::Deflate(inner)),
+                                Err(e) => e,
+                            })
+                        }
+                    };
+
+                    if n > 0 {
+                        self.crc.update(&buf[0..n]);
+
+                        return Ok(n);
+                    }
+
+                    self.read_trailer()?;
+
+                    //Another member may follow
+                    self.state = if self.inflater.raw_eof()? { State::Done } else { State::Header };
+                }
+            }
+        }
+    }
+}
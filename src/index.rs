@@ -0,0 +1,113 @@
+//!Parsing in two steps: first find where each record starts and ends, then split the fields of only the records
+//!that are needed. This is faster when most records are skipped by looking at one field, or when only the first
+//!few fields of each record are used.
+
+use std::ops::Range;
+
+use crate::{encoding, scan, ParseStatus, Parser};
+
+///The positions of the records of CSV data, as created by ``Parser::index_records()``. The index takes 8 bytes per
+///record. It holds no reference to the data, so the data must be passed again to get a record.
+///
+/// # Example
+/// ```
+/// let data = b"id,status\n1,N\n2,Y\n3,N\n";
+/// let mut parser = rcsv::Parser::new();
+/// let index = parser.index_records(data);
+/// let mut fields: [&[u8]; 2] = [&[]; 2];
+///
+/// assert!(index.len() == 4);
+///
+/// //Only split the records that end with Y
+/// for i in 1..index.len() {
+///     let record = index.record(data, i);
+///
+///     if record.ends_with(b"Y\n") {
+///         assert!(parser.split_record(record, &mut fields) == 2);
+///         assert!(fields[0] == b"2");
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordIndex {
+    //The start of every record and the end of the last one
+    bounds: Vec<usize>,
+}
+
+impl RecordIndex {
+    ///Returns the number of records.
+    pub fn len(&self) -> usize {
+        self.bounds.len().saturating_sub(1)
+    }
+
+    ///Returns true if there are no records.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///Returns the position of record ``i`` in the data, including its line end. Panics if ``i`` is not less than ``len()``.
+    pub fn range(&self, i: usize) -> Range<usize> {
+        self.bounds[i]..self.bounds[i + 1]
+    }
+
+    ///Returns record ``i`` of ``data``, including its line end. ``data`` must be the data that was indexed.
+    pub fn record<'a>(&self, data: &'a [u8], i: usize) -> &'a [u8] {
+        &data[self.range(i)]
+    }
+}
+
+impl Parser {
+    /// Finds where each record of ``data`` starts and ends without splitting the fields. The records are those
+    /// ``parse()`` reports. Split a record with ``split_record()``.
+    pub fn index_records(&self, data: &[u8]) -> RecordIndex {
+        let start = encoding::utf8_bom_len(data);
+        let mut bounds = vec![start];
+
+        scan::follow(data, start, false, &self.dialect, |end| bounds.push(end));
+
+        if bounds.len() == 1 {
+            bounds.clear();
+        }
+
+        RecordIndex { bounds }
+    }
+
+    /// Splits a ``record`` found by ``index_records()`` into ``fields`` and returns the number of fields found.
+    /// At most ``fields.len()`` fields are split, the rest of the record is not looked at. Pass a shorter slice
+    /// of ``fields`` to split only the first few fields.
+    ///
+    /// # Example
+    /// ```
+    /// let mut parser = rcsv::Parser::new();
+    /// let mut fields: [&[u8]; 4] = [&[]; 4];
+    ///
+    /// assert!(parser.split_record(b"aa,\"b,b\",cc\r\n", &mut fields) == 3);
+    /// assert!(fields[1] == b"b,b");
+    ///
+    /// //Only the first field
+    /// assert!(parser.split_record(b"dd,ee,ff\r\n", &mut fields[..1]) == 1);
+    /// assert!(fields[0] == b"dd");
+    /// ```
+    pub fn split_record<'a>(&mut self, record: &'a [u8], fields: &mut [&'a [u8]]) -> usize {
+        let mut field_index: usize = 0;
+
+        self.position = 0;
+
+        while field_index < fields.len() {
+            let status = self.next_field(record);
+
+            if let ParseStatus::EndDocument = status {
+                break;
+            }
+
+            fields[field_index] = self.field(record);
+            field_index += 1;
+
+            if let ParseStatus::EndRecord = status {
+                break;
+            }
+        }
+
+        field_index
+    }
+}
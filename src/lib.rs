@@ -3,29 +3,184 @@
 //!parse very large CSV files with a constant memory cost.
 
 pub mod mmap;
+pub mod stream;
+pub mod dataset;
+pub mod encoding;
+pub mod utf8;
+pub mod sniff;
+pub mod index;
+pub mod batch;
+pub mod number;
+mod error;
+mod scan;
+#[cfg(feature = "deflate")]
+pub mod deflate;
+#[cfg(feature = "gzip")]
+pub mod gzip;
+#[cfg(feature = "zip")]
+pub mod zip;
+
+pub use error::Error;
+
+use std::fmt;
+
 enum ParseStatus {
     HasMoreFields,
     EndRecord,
     EndDocument,
 }
 
+///The characters that give CSV data its structure. The default is the RFC 4180 dialect: fields are separated by
+///a comma, escaped by double quotes and a double quote inside an escaped field is written as two double quotes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    ///The character that separates fields. A comma by default.
+    pub delimiter: u8,
+    ///The character that encloses escaped fields. A double quote by default.
+    pub quote: u8,
+    ///A character that makes the character after it lose its special meaning, such as ``\`` in ``"say \"hi\""``.
+    ///None by default. Like doubled quotes, escape characters are not removed from the field.
+    pub escape: Option<u8>,
+}
+
+impl Dialect {
+    ///Checks that the dialect can be parsed. The delimiter, quote and escape characters must be ASCII, must not be
+    ///a CR or a LF and must differ from each other. The parser relies on this: it only ever splits the data at
+    ///ASCII characters and tells the characters apart by value alone.
+    ///
+    /// # Example
+    /// ```
+    /// use rcsv::{Dialect, DialectError};
+    ///
+    /// assert!(Dialect { delimiter: b';', ..Default::default() }.validate().is_ok());
+    /// assert!(Dialect { delimiter: b'"', ..Default::default() }.validate() == Err(DialectError::Clash(b'"')));
+    /// ```
+    pub fn validate(&self) -> Result<(), DialectError> {
+        let chars = [Some(self.delimiter), Some(self.quote), self.escape];
+
+        for (i, &ch) in chars.iter().enumerate() {
+            let Some(ch) = ch else {
+                continue;
+            };
+
+            if !ch.is_ascii() {
+                return Err(DialectError::NotAscii(ch));
+            }
+
+            if ch == b'\r' || ch == b'\n' {
+                return Err(DialectError::LineEnd(ch));
+            }
+
+            if chars[0..i].contains(&Some(ch)) {
+                return Err(DialectError::Clash(ch));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Dialect {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+        }
+    }
+}
+
+///Why a dialect was rejected by ``Dialect::validate()`` and ``Parser::with_dialect()``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialectError {
+    ///The character is not ASCII. It could be a part of a multi-byte UTF-8 sequence.
+    NotAscii(u8),
+    ///The character is a CR or a LF. They always end a record.
+    LineEnd(u8),
+    ///The character is used for more than one of the delimiter, the quote and the escape.
+    Clash(u8),
+}
+
+impl fmt::Display for DialectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialectError::NotAscii(ch) => write!(f, "The dialect character 0x{:02x} is not ASCII.", ch),
+            DialectError::LineEnd(ch) => write!(f, "The dialect character 0x{:02x} is a line end.", ch),
+            DialectError::Clash(ch) => write!(f, "The dialect character 0x{:02x} is used more than once.", ch),
+        }
+    }
+}
+
+impl std::error::Error for DialectError {}
+
 ///The parser of CSV data.
 pub struct Parser {
     start: usize,
     stop: usize,
     position: usize,
+    dialect: Dialect,
+    //The bytes next_field() stops at outside and inside a quoted field
+    outside: scan::Needles,
+    inside: scan::Needles,
+}
+
+///The number of records and lines in CSV data, as returned by ``Parser::count_records()``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RecordCount {
+    ///The number of records that ``Parser::parse()`` reports.
+    pub records: usize,
+    ///The number of line ends, including those inside quoted fields. A CR LF pair is one line end. Like records,
+    ///an unterminated last line is not counted.
+    pub lines: usize,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
 }
 
 impl Parser {
     /// Creates a new parser.
     pub fn new() -> Parser {
+        Parser::with_valid_dialect(Dialect::default())
+    }
+
+    /// Creates a new parser of CSV data in the given ``dialect``, such as semicolon separated data. Returns an
+    /// error if the dialect is rejected by ``Dialect::validate()``.
+    /// 
+    /// # Example
+    /// ```
+    /// let dialect = rcsv::Dialect { delimiter: b';', ..Default::default() };
+    /// let mut parser = rcsv::Parser::with_dialect(dialect).unwrap();
+    ///
+    /// parser.parse::<3>("aa;\"b;b\";cc\r\n".as_bytes(), |_, fields| {
+    ///     assert!(fields[1] == "b;b".as_bytes());
+    /// });
+    /// ```
+    pub fn with_dialect(dialect: Dialect) -> Result<Parser, DialectError> {
+        dialect.validate()?;
+
+        Ok(Parser::with_valid_dialect(dialect))
+    }
+
+    ///Creates a parser without checking ``dialect``. It must be one that ``Dialect::validate()`` accepts.
+    fn with_valid_dialect(dialect: Dialect) -> Parser {
         Parser {
             start: 0,
             stop: 0,
             position: 0,
+            dialect,
+            outside: scan::Needles::outside(&dialect),
+            inside: scan::Needles::inside(&dialect),
         }
     }
 
+    /// Returns the dialect of the data parsed by this parser.
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
     fn peek(&self, data: &[u8]) -> Option<u8> {
         if self.position < data.len() {
             Some(data[self.position])
@@ -44,12 +199,6 @@ impl Parser {
         }
     }
 
-    fn putback(&mut self) {
-        if self.position > 0 {
-            self.position -= 1;
-        }
-    }
-
     fn mark_start(&mut self) {
         self.start = self.position;
     }
@@ -69,15 +218,28 @@ impl Parser {
     fn next_field(&mut self, data: &[u8]) -> ParseStatus {
         let mut inside_dquote = false;
         let mut escaped_field = false;
-        let dquote: u8 = 34;
-        let comma: u8 = 44;
+        let dquote: u8 = self.dialect.quote;
+        let comma: u8 = self.dialect.delimiter;
+        let escape: Option<u8> = self.dialect.escape;
         let cr: u8 = 13;
         let lf: u8 = 10;
 
         self.mark_start();
 
         loop {
+            //Skip the bytes that mean nothing here, a block at a time
+            self.position = scan::find(data, self.position, if inside_dquote { &self.inside } else { &self.outside });
+
             if let Some(ch) = self.pop(data) {
+                if Some(ch) == escape {
+                    //The next character is taken literally
+                    if self.pop(data).is_none() {
+                        return ParseStatus::EndDocument;
+                    }
+
+                    continue;
+                }
+
                 if ch == dquote {
                     if !inside_dquote {
                         inside_dquote = true;
@@ -174,6 +336,32 @@ impl Parser {
         }
     }
 
+    /// Parses the records in ``data`` that are known to be complete and returns the number of bytes consumed
+    /// and the number of records reported. Parsing stops at the first incomplete record. Unless ``eof`` is true,
+    /// a record that ends with a CR at the very end of ``data`` is treated as incomplete since the LF may follow.
+    /// 
+    /// This is used by the drivers that feed the parser a piece of the input at a time.
+    pub(crate) fn parse_complete<const N: usize>(&mut self, data: &[u8], eof: bool, first_index: usize, consumer: &mut impl FnMut(usize, &[&[u8]])) -> (usize, usize) {
+        let mut fields: [&[u8]; N] = [&[]; N];
+        let mut consumed: usize = 0;
+        let mut count: usize = 0;
+
+        self.position = 0;
+
+        while let Some(field_count) = self.parse_record(data, &mut fields) {
+            if !eof && self.position == data.len() && data[data.len() - 1] == b'\r' {
+                break;
+            }
+
+            consumer(first_index + count, &fields[0..field_count]);
+
+            consumed = self.position;
+            count += 1;
+        }
+
+        (consumed, count)
+    }
+
     /// Begins parsing CSV ``data``. For every record (line in CSV), the ``consumer`` closure is called.
     /// The generic parameter ``N`` determines the maximum number of fields (columns) that will be passed
     /// to the closure. If the record has more fields then the excess fields are silently ignored.
@@ -183,6 +371,8 @@ impl Parser {
     /// - The index of the record. The first line has an index of 0.
     /// - An array slice of fields ``&[ &[u8] ]``. Each field is an array of unsigned bytes ``&[u8]``.
     /// 
+    /// A UTF-8 byte order mark at the start of ``data`` is skipped.
+    /// 
     /// # Example
     ///  ```
     /// fn test_uneven() {
@@ -222,12 +412,37 @@ impl Parser {
         let mut fields: [&[u8]; N] = [&[]; N];
         let mut index: usize = 0;
 
+        //Skip the UTF-8 byte order mark
+        if self.position == 0 {
+            self.position = encoding::utf8_bom_len(data);
+        }
+
         while let Some(field_count) = self.parse_record(data, &mut fields) {
             consumer(index, &fields[0..field_count]);
 
             index += 1;
         }
     }
+
+    /// Counts the records and the lines of ``data`` without splitting the fields. Only the quoting and the line ends
+    /// are followed, skipping the other bytes a block at a time. The records are those ``parse()`` reports:
+    /// an unterminated last record is not counted and line ends inside quoted fields do not end a record.
+    ///
+    /// # Example
+    /// ```
+    /// let count = rcsv::Parser::new().count_records(b"id,text\r\n1,\"two\nlines\"\r\n2,unterminated");
+    ///
+    /// assert!(count.records == 2);
+    /// assert!(count.lines == 3);
+    /// ```
+    pub fn count_records(&self, data: &[u8]) -> RecordCount {
+        let start = encoding::utf8_bom_len(data);
+
+        RecordCount {
+            records: scan::trace(data, start, false, &self.dialect).records,
+            lines: scan::count_lines(data),
+        }
+    }
 }
 
 ///Utility function that parses the ``bytes`` array slice to a number ``n``.
@@ -235,10 +450,13 @@ impl Parser {
 /// 
 /// Parsing is done internally by the ``std::str::parse()`` method.
 /// 
-/// As an optimization, the ``bytes`` array slice ``&[u8]`` is converted to ``&str`` without
-/// validating for UTF-8. This is OK to do since the number parser does its own validation.
+/// The ``bytes`` array slice ``&[u8]`` is validated as UTF-8 before it is converted to ``&str``.
+/// Invalid UTF-8 is reported as a failed conversion.
 /// 
 /// Spaces around the string are trimmed to avoid errors from the number parser.
+/// 
+/// The parsers in ``rcsv::number``, such as ``rcsv::number::parse_f64()``, work on the bytes directly. They are
+/// faster and their error tells which byte is wrong.
 ///  
 /// # Example
 /// ```
@@ -268,15 +486,12 @@ impl Parser {
 /// }
 /// ```
 pub fn parse_number<T: std::str::FromStr>(bytes:&[u8], n: &mut T) -> bool {
-    unsafe {
-        match std::str::from_utf8_unchecked(bytes).trim()
-            .parse::<T>() {
-            Ok(v) => {
-                *n = v;
-
-                true
-            },
-            Err(_) => false
-        }
+    match std::str::from_utf8(bytes).map(|s| s.trim().parse::<T>()) {
+        Ok(Ok(v)) => {
+            *n = v;
+
+            true
+        },
+        _ => false
     }
 }
\ No newline at end of file
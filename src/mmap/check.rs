@@ -0,0 +1,63 @@
+use std::fs::File;
+use std::io;
+use std::time::SystemTime;
+
+///How a mapped file changed since it was mapped, as reported by ``FileMapper::check()``.
+///
+///Changes made by other processes are visible in a shared mapping. If the file shrank, reading the pages past its
+///new end kills the process with ``SIGBUS`` on Unix. Results parsed from a changed file should not be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileChange {
+    ///The size and modification time are as they were when the file was mapped.
+    Unchanged,
+    ///The file is larger. The data appended is not mapped. The mapped data may have changed too.
+    Grown { size: u64 },
+    ///The file is smaller. Reading the mapped data past ``size`` is fatal unless the mapping is a snapshot.
+    Truncated { size: u64 },
+    ///The size is the same but the file was written to. The mapped data may have changed.
+    Modified,
+}
+
+impl FileChange {
+    ///Returns true if the file has not changed.
+    pub fn is_unchanged(&self) -> bool {
+        *self == FileChange::Unchanged
+    }
+}
+
+///The size and modification time of ``file`` when it was mapped.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FileStamp {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    pub(crate) fn new(file: &File) -> io::Result<FileStamp> {
+        let metadata = file.metadata()?;
+
+        Ok(FileStamp {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    ///Compares the current size and modification time of ``file`` to the stamp.
+    pub(crate) fn compare(&self, file: &File) -> io::Result<FileChange> {
+        let now = FileStamp::new(file)?;
+
+        Ok(if now.size < self.size {
+            FileChange::Truncated { size: now.size }
+        } else if now.size > self.size {
+            FileChange::Grown { size: now.size }
+        } else if now.modified != self.modified {
+            FileChange::Modified
+        } else {
+            FileChange::Unchanged
+        })
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+///The step of memory mapping a file that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapStep {
+    ///Opening the file in read-only mode.
+    Open,
+    ///Getting the size of the file, ``fstat()`` or ``GetFileSize()``.
+    Stat,
+    ///Mapping the file, ``mmap()`` or ``CreateFileMappingW()``.
+    Map,
+    ///Getting a view of the mapping, ``MapViewOfFile()``. Windows only.
+    View,
+    ///Giving the access pattern advice of ``MapOptions``, ``madvise()``.
+    Advise,
+    ///Locking the pages in memory, ``mlock()`` or ``VirtualLock()``.
+    Lock,
+    ///Taking the shared advisory lock on the file of ``MapOptions::shared_lock()``, ``flock()``.
+    FileLock,
+}
+
+impl fmt::Display for MapStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MapStep::Open => "open",
+            MapStep::Stat => "stat",
+            MapStep::Map => "map",
+            MapStep::View => "map view",
+            MapStep::Advise => "advise",
+            MapStep::Lock => "lock",
+            MapStep::FileLock => "file lock",
+        };
+
+        f.write_str(name)
+    }
+}
+
+///The error returned when a file can not be memory mapped. It has the path of the file, the step that failed and
+///the operating system error.
+///
+/// # Example
+/// ```
+/// use std::io::ErrorKind;
+///
+/// match rcsv::mmap::FileMapper::new("does-not-exist.csv") {
+///     Ok(_) => panic!("The file should not exist."),
+///     Err(e) => {
+///         assert!(e.kind() == ErrorKind::NotFound);
+///         assert!(e.step() == rcsv::mmap::MapStep::Open);
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct MapError {
+    path: PathBuf,
+    step: MapStep,
+    source: io::Error,
+}
+
+impl MapError {
+    pub(crate) fn new(path: &Path, step: MapStep, source: io::Error) -> MapError {
+        MapError {
+            path: path.to_path_buf(),
+            step,
+            source,
+        }
+    }
+
+    ///Returns the path of the file that could not be mapped. It is empty if the file was opened by the caller,
+    ///as in ``FileMapper::from_file()``.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    ///Returns the step that failed.
+    pub fn step(&self) -> MapStep {
+        self.step
+    }
+
+    ///Returns the kind of the operating system error, such as ``ErrorKind::NotFound`` or ``ErrorKind::PermissionDenied``.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+
+    ///Returns the operating system error.
+    pub fn io_error(&self) -> &io::Error {
+        &self.source
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //The path is empty for a file that was opened by the caller
+        if self.path.as_os_str().is_empty() {
+            return write!(f, "Failed to map file. The {} step failed. {}", self.step, self.source);
+        }
+
+        write!(f, "Failed to map file {}. The {} step failed. {}", self.path.display(), self.step, self.source)
+    }
+}
+
+impl std::error::Error for MapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<MapError> for io::Error {
+    ///Keeps the kind of the operating system error. The ``MapError`` can be recovered with ``io::Error::into_inner()``.
+    fn from(e: MapError) -> Self {
+        io::Error::new(e.kind(), e)
+    }
+}
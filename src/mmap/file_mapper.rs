@@ -1,20 +1,53 @@
 #[cfg(unix)]
 pub mod unix_map {
-    use std::{fs::File, os::fd::AsRawFd};
+    use std::{fs::File, io, ops::Range, os::fd::{AsRawFd, OwnedFd}, os::unix::fs::FileExt, path::Path};
+
+    use crate::mmap::check::FileStamp;
+    use crate::mmap::{Advice, FileChange, MapError, MapOptions, MapStep};
+
+    ///Returns the size of a memory page. Mappings and hints work on whole pages.
+    pub(crate) fn page_size() -> usize {
+        unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+    }
+
+    fn madvise_flag(advice: Advice) -> libc::c_int {
+        match advice {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+            Advice::Random => libc::MADV_RANDOM,
+            Advice::WillNeed => libc::MADV_WILLNEED,
+        }
+    }
 
     ///Performs memory mapping of a file.
     pub struct FileMapper {
+        //The length of the data, the whole file or the requested range
         file_size: libc::size_t,
+        //The start of the mapping. Null for empty data. mmap() does not accept a length of 0.
         ptr: *mut libc::c_void,
+        //The length of the mapping and the position of the data in it. A range of the file is mapped from
+        //the start of its first page.
+        map_len: usize,
+        skew: usize,
+        //The offset of the data in the file and the byte before it, if any
+        offset: u64,
+        previous: Option<u8>,
+        //Kept open for the lifetime of the mapping. Also holds the flock() lock, if any.
         file: File,
+        stamp: FileStamp,
+        //The mapping is an anonymous copy of the file
+        snapshot: bool,
     }
 
     impl FileMapper {
-        ///Creates a new ``FileMapper`` that maps the file pointed to by ``file_name``. The file is mapped in read-only mode.
-        /// 
+        ///Creates a new ``FileMapper`` that maps the file at ``path``. The file is mapped in read-only mode.
+        ///An empty file is not mapped and ``get_bytes()`` returns an empty slice.
+        ///If the file can not be mapped, the returned ``MapError`` has the path, the step that failed and the operating
+        ///system error.
+        ///
         /// # Example
         /// The example below memory maps a CSV file ``test.csv`` and parses the CSV.
-        /// 
+        ///
         /// ```
         ///fn test_memory_map_reader() {
         ///    let mapper = match rcsv::mmap::FileMapper::new("test.csv") {
@@ -33,60 +66,322 @@ pub mod unix_map {
         ///}
         ///```
         ///
-        pub fn new(file_name: &str) -> Result<FileMapper, &str> {
+        pub fn new<P: AsRef<Path>>(path: P) -> Result<FileMapper, MapError> {
+            FileMapper::with_options(path, &MapOptions::new())
+        }
 
-            let file = match File::open(file_name) {
-                Ok(f) => f,
-                Err(_) => return Err("Failed to open file in readonly mode.")
-            };
-    
+        ///Maps the file at ``path`` with access pattern hints and other options. See ``MapOptions``.
+        pub fn with_options<P: AsRef<Path>>(path: P, options: &MapOptions) -> Result<FileMapper, MapError> {
+            let path = path.as_ref();
+            let file = File::open(path).map_err(|e| MapError::new(path, MapStep::Open, e))?;
+
+            FileMapper::map(file, path, options, None)
+        }
+
+        ///Maps ``len`` bytes of the file at ``path`` starting at ``offset``. ``offset`` does not need to be page aligned.
+        ///``get_bytes()`` returns exactly the requested bytes, or fewer if the range goes past the end of the file.
+        ///The range usually starts in the middle of a record, see ``first_record_offset()``.
+        ///
+        /// # Example
+        /// ```
+        /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+        /// let mapper = rcsv::mmap::FileMapper::map_range(path, 4, 100).unwrap();
+        ///
+        /// assert!(mapper.get_bytes().starts_with(b"b,cc\n"));
+        /// assert!(&mapper.get_bytes()[mapper.first_record_offset()..] == b"dd,ee,ff\ngg,hh,ii");
+        /// ```
+        pub fn map_range<P: AsRef<Path>>(path: P, offset: u64, len: usize) -> Result<FileMapper, MapError> {
+            MapOptions::new().map_range(path, offset, len)
+        }
+
+        ///Maps a file that is already open, for example one opened with special flags. The file must be open for reading.
+        ///The path of a ``MapError`` returned by this function is empty.
+        pub fn from_file(file: File) -> Result<FileMapper, MapError> {
+            FileMapper::map(file, Path::new(""), &MapOptions::new(), None)
+        }
+
+        ///Maps the file of an open file descriptor, such as one received over a Unix domain socket.
+        ///The file descriptor must be open for reading. It is closed when the ``FileMapper`` is dropped.
+        pub fn from_fd(fd: OwnedFd) -> Result<FileMapper, MapError> {
+            FileMapper::from_file(File::from(fd))
+        }
+
+        ///Maps the whole file, or the bytes in ``range`` clipped to the end of the file.
+        pub(crate) fn map(file: File, path: &Path, options: &MapOptions, range: Option<(u64, usize)>) -> Result<FileMapper, MapError> {
             unsafe {
-                let mut sbuf : libc::stat = std::mem::zeroed();
-    
-                if libc::fstat(file.as_raw_fd(), &mut sbuf) < 0 {
-                    return Err("Failed to get file size. fstat() failed.");
-                }
-    
-                let file_size = sbuf.st_size as libc::size_t;
-    
-                let ptr = libc::mmap(
-                    std::ptr::null_mut(),
+                //Before the size is read, so that a cooperating writer can not truncate the file in between
+                if options.shared_lock {
+                    while libc::flock(file.as_raw_fd(), libc::LOCK_SH) < 0 {
+                        let e = io::Error::last_os_error();
+
+                        if e.kind() != io::ErrorKind::Interrupted {
+                            return Err(MapError::new(path, MapStep::FileLock, e));
+                        }
+                    }
+                }
+
+                let stamp = FileStamp::new(&file).map_err(|e| MapError::new(path, MapStep::Stat, e))?;
+                let (offset, len) = range.unwrap_or((0, usize::MAX));
+                let offset = offset.min(stamp.size());
+                let file_size = (stamp.size() - offset).min(len as u64) as libc::size_t;
+                //Snapshots are not mapped from the file and need no alignment
+                let skew = if options.snapshot { 0 } else { (offset % page_size() as u64) as usize };
+                let mut mapper = FileMapper {
                     file_size,
-                    libc::PROT_READ,
-                    libc::MAP_FILE | libc::MAP_SHARED,
-                    file.as_raw_fd(),
-                    0,
-                );    
-        
-                if ptr == libc::MAP_FAILED {
-                    return Err("Failed to map file. mmap() failed.");
+                    ptr: std::ptr::null_mut(),
+                    map_len: file_size + skew,
+                    skew,
+                    offset,
+                    previous: None,
+                    file,
+                    stamp,
+                    snapshot: options.snapshot,
+                };
+
+                if offset > 0 {
+                    let mut previous = [0u8; 1];
+
+                    mapper.file.read_exact_at(&mut previous, offset - 1).map_err(|e| MapError::new(path, MapStep::Map, e))?;
+                    mapper.previous = Some(previous[0]);
                 }
-    
-                Ok(
-                    FileMapper {
+
+                if file_size == 0 {
+                    return Ok(mapper);
+                }
+
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                let populate = if options.populate { libc::MAP_POPULATE } else { 0 };
+                #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                let populate = 0;
+
+                //A snapshot is an anonymous private mapping that the file is copied into. Copy-on-write pages of a
+                //private file mapping are not enough, Linux discards them when the file is truncated.
+                let ptr = if options.snapshot {
+                    libc::mmap(
+                        std::ptr::null_mut(),
                         file_size,
-                        ptr,
-                        file, 
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_PRIVATE | libc::MAP_ANON,
+                        -1,
+                        0,
+                    )
+                } else {
+                    libc::mmap(
+                        std::ptr::null_mut(),
+                        mapper.map_len,
+                        libc::PROT_READ,
+                        libc::MAP_FILE | libc::MAP_SHARED | populate,
+                        mapper.file.as_raw_fd(),
+                        (offset - skew as u64) as libc::off_t,
+                    )
+                };
+
+                if ptr == libc::MAP_FAILED {
+                    return Err(MapError::new(path, MapStep::Map, io::Error::last_os_error()));
+                }
+
+                //Unmapped by drop() if a later step fails
+                mapper.ptr = ptr;
+
+                if options.snapshot {
+                    let copy = std::slice::from_raw_parts_mut(ptr as *mut u8, file_size);
+
+                    //Read with pread() since the file position of a caller supplied file must not move
+                    mapper.file.read_exact_at(copy, offset).map_err(|e| MapError::new(path, MapStep::Map, e))?;
+
+                    if libc::mprotect(ptr, file_size, libc::PROT_READ) < 0 {
+                        return Err(MapError::new(path, MapStep::Map, io::Error::last_os_error()));
+                    }
+                }
+
+                if options.advice != Advice::Normal {
+                    mapper.advise(0..file_size, options.advice).map_err(|e| MapError::new(path, MapStep::Advise, e))?;
+                }
+
+                #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                if options.populate {
+                    mapper.advise(0..file_size, Advice::WillNeed).map_err(|e| MapError::new(path, MapStep::Advise, e))?;
+                }
+
+                //Best effort. Fails if the kernel has no transparent huge page support.
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                if options.huge_pages {
+                    libc::madvise(ptr, mapper.map_len, libc::MADV_HUGEPAGE);
+                }
+
+                if options.lock {
+                    mapper.lock(0..file_size).map_err(|e| MapError::new(path, MapStep::Lock, e))?;
+                }
+
+                Ok(mapper)
+            }
+        }
+
+        ///Returns the pointer and length of the whole pages that cover ``range``, clipped to the data.
+        fn page_range(&self, range: Range<usize>) -> Option<(*mut libc::c_void, usize)> {
+            let end = self.skew + range.end.min(self.file_size);
+            let start = self.skew + range.start;
+            let start = start - start % page_size();
+
+            if self.ptr.is_null() || start >= end {
+                return None;
+            }
+
+            Some((unsafe { (self.ptr as *mut u8).add(start) as *mut libc::c_void }, end - start))
+        }
+
+        ///Changes the access pattern advice for the bytes in ``range``, such as when switching from a sequential
+        ///scan to random lookups in part of the file. The range is extended to whole pages.
+        pub fn advise(&self, range: Range<usize>, advice: Advice) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if unsafe { libc::madvise(ptr, len, madvise_flag(advice)) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        ///Locks the pages of the bytes in ``range`` in memory. The pages are read in if needed.
+        pub fn lock(&self, range: Range<usize>) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if unsafe { libc::mlock(ptr, len) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        ///Unlocks the pages of the bytes in ``range`` locked by ``lock()`` or ``MapOptions::lock()``.
+        pub fn unlock(&self, range: Range<usize>) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if unsafe { libc::munlock(ptr, len) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        ///Returns the position in the mapping and length of the whole pages inside ``range``. The partial last page of
+        ///the data counts as whole.
+        fn inner_page_range(&self, range: Range<usize>) -> Option<(usize, usize)> {
+            let page = page_size();
+            let start = (self.skew + range.start).div_ceil(page) * page;
+            let end = if range.end >= self.file_size { self.map_len } else { (self.skew + range.end) / page * page };
+
+            if self.ptr.is_null() || start >= end {
+                return None;
+            }
+
+            Some((start, end - start))
+        }
+
+        ///Drops the pages of the bytes in ``range`` from memory, both from this process (``MADV_DONTNEED``) and,
+        ///on Linux, from the page cache (``POSIX_FADV_DONTNEED``). Only the pages entirely inside ``range`` are released.
+        ///The data stays readable. Released pages are read from the file again if accessed.
+        ///
+        ///Nothing is released from a snapshot mapping since it would lose the private copy.
+        pub fn release(&self, range: Range<usize>) -> io::Result<()> {
+            let (start, len) = match self.inner_page_range(range) {
+                Some(r) if !self.snapshot => r,
+                _ => return Ok(()),
+            };
+
+            unsafe {
+                if libc::madvise((self.ptr as *mut u8).add(start) as *mut libc::c_void, len, libc::MADV_DONTNEED) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                //Clean pages only. Fails silently for pages still mapped by other processes.
+                #[cfg(any(target_os = "linux", target_os = "android"))]
+                {
+                    let file_offset = self.offset - self.skew as u64 + start as u64;
+                    let result = libc::posix_fadvise(self.file.as_raw_fd(), file_offset as libc::off_t, len as libc::off_t, libc::POSIX_FADV_DONTNEED);
+
+                    if result != 0 {
+                        return Err(io::Error::from_raw_os_error(result));
                     }
-                )
+                }
+            }
+
+            Ok(())
+        }
+
+        ///Returns the number of bytes of the mapped data that are in memory (``mincore()``), in whole pages.
+        pub fn resident_size(&self) -> io::Result<usize> {
+            if self.ptr.is_null() {
+                return Ok(0);
             }
+
+            let page = page_size();
+            let mut pages = vec![0u8; self.map_len.div_ceil(page)];
+
+            if unsafe { libc::mincore(self.ptr, self.map_len, pages.as_mut_ptr() as *mut _) } < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(pages.iter().filter(|&&p| p & 1 != 0).count() * page)
+        }
+
+        ///Compares the size and modification time of the file to those when it was mapped. Call this after parsing,
+        ///before trusting the results, to detect a file that was changed by another process in the meantime.
+        pub fn check(&self) -> io::Result<FileChange> {
+            self.stamp.compare(&self.file)
         }
 
-        ///Returns all the data in the file as byte array slice ``&[u8]``.
+        ///Returns all the data in the file, or in the range mapped by ``map_range()``, as byte array slice ``&[u8]``.
         pub fn get_bytes(&self) -> &[u8] {
-            unsafe {std::slice::from_raw_parts(self.ptr as *const u8, self.file_size)}
+            if self.ptr.is_null() {
+                return &[];
+            }
+
+            unsafe {std::slice::from_raw_parts((self.ptr as *const u8).add(self.skew), self.file_size)}
         }
 
-        ///Returns the size of the file. This is same as the length of the array slice returned by ``get_bytes()``.
+        ///Returns the size of the file, or of the range mapped by ``map_range()``. This is same as the length of the
+        ///array slice returned by ``get_bytes()``.
         pub fn size(&self) -> usize {
             self.file_size
         }
+
+        ///Returns the offset in the file of the first byte of ``get_bytes()``. It is 0 unless mapped by ``map_range()``.
+        pub fn offset(&self) -> u64 {
+            self.offset
+        }
+
+        ///Returns the position in ``get_bytes()`` of the first record that starts in the mapped range. It is 0 if the
+        ///range starts at the start of the file or right after a line end. Otherwise it is the position after the first
+        ///line end in the range, or the size of the range if there is none. The bytes before it belong to a record that
+        ///starts before the range. Line ends inside quoted fields are not told apart from those between records.
+        pub fn first_record_offset(&self) -> usize {
+            crate::mmap::record_start_after(self.previous, self.get_bytes(), 0)
+        }
     }
 
-    impl <'a> Drop for FileMapper {
+    //The mapping is read-only (a snapshot is made read-only before it is shared) and is only unmapped on drop.
+    //Reading it from many threads is safe.
+    unsafe impl Send for FileMapper {}
+    unsafe impl Sync for FileMapper {}
+
+    impl Drop for FileMapper {
         fn drop(&mut self) {
+            if self.ptr.is_null() {
+                return;
+            }
+
             unsafe {
-                libc::munmap(self.ptr, self.file_size);
+                libc::munmap(self.ptr, self.map_len);
             }
         }
     }
@@ -95,32 +390,118 @@ pub mod unix_map {
 
 #[cfg(windows)]
 pub mod windows_map {
-    use std::{fs::File};
-    use std::os::windows::io::{AsRawHandle};
-    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use std::{fs::File, io, ops::Range, path::Path};
+    use std::os::windows::fs::FileExt;
+    use std::os::windows::io::{AsRawHandle, OwnedHandle};
+    use winapi::um::handleapi::CloseHandle;
     use winapi::um::winnt::HANDLE;
-    use winapi::um::fileapi::{GetFileSize};
     use winapi::um::memoryapi::{
         CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_READ,
+        PrefetchVirtualMemory, VirtualLock, VirtualUnlock, WIN32_MEMORY_RANGE_ENTRY,
     };
+    use winapi::um::processthreadsapi::GetCurrentProcess;
+    use winapi::um::sysinfoapi::{GetSystemInfo, SYSTEM_INFO};
     use winapi::um::winnt::PAGE_READONLY;
 
+    use crate::mmap::check::FileStamp;
+    use crate::mmap::{Advice, FileChange, MapError, MapOptions, MapStep};
+
+    pub(crate) fn page_size() -> usize {
+        unsafe {
+            let mut info: SYSTEM_INFO = std::mem::zeroed();
+
+            GetSystemInfo(&mut info);
+
+            info.dwPageSize as usize
+        }
+    }
+
+    ///Returns the alignment of the file offset of a view, usually 64K.
+    pub(crate) fn allocation_granularity() -> usize {
+        unsafe {
+            let mut info: SYSTEM_INFO = std::mem::zeroed();
+
+            GetSystemInfo(&mut info);
+
+            info.dwAllocationGranularity as usize
+        }
+    }
+
     pub struct FileMapper {
         file_size: usize,
+        //Both null for empty data. CreateFileMappingW() fails for an empty file.
         map_handle: HANDLE,
         ptr: *mut winapi::ctypes::c_void,
+        //A view starts at a multiple of the allocation granularity
+        map_len: usize,
+        skew: usize,
+        offset: u64,
+        previous: Option<u8>,
+        //Kept open for the lifetime of the mapping.
         file: File,
+        stamp: FileStamp,
     }
 
     impl FileMapper {
-        pub fn new(file_name: &str) -> Result<FileMapper, &str> {
+        pub fn new<P: AsRef<Path>>(path: P) -> Result<FileMapper, MapError> {
+            FileMapper::with_options(path, &MapOptions::new())
+        }
 
-            let file = match File::open(file_name) {
-                Ok(f) => f,
-                Err(_) => return Err("Failed to open file in readonly mode.")
-            };
-    
+        pub fn with_options<P: AsRef<Path>>(path: P, options: &MapOptions) -> Result<FileMapper, MapError> {
+            let path = path.as_ref();
+            let file = File::open(path).map_err(|e| MapError::new(path, MapStep::Open, e))?;
+
+            FileMapper::map(file, path, options, None)
+        }
+
+        pub fn map_range<P: AsRef<Path>>(path: P, offset: u64, len: usize) -> Result<FileMapper, MapError> {
+            MapOptions::new().map_range(path, offset, len)
+        }
+
+        pub fn from_file(file: File) -> Result<FileMapper, MapError> {
+            FileMapper::map(file, Path::new(""), &MapOptions::new(), None)
+        }
+
+        pub fn from_handle(handle: OwnedHandle) -> Result<FileMapper, MapError> {
+            FileMapper::from_file(File::from(handle))
+        }
+
+        //Windows does not allow truncating a file that is mapped, so ``shared_lock`` and ``snapshot`` are not needed
+        pub(crate) fn map(file: File, path: &Path, options: &MapOptions, range: Option<(u64, usize)>) -> Result<FileMapper, MapError> {
             unsafe {
+                let stamp = FileStamp::new(&file).map_err(|e| MapError::new(path, MapStep::Stat, e))?;
+                let (offset, len) = range.unwrap_or((0, usize::MAX));
+                let offset = offset.min(stamp.size());
+                let file_size = (stamp.size() - offset).min(len as u64) as usize;
+                let skew = (offset % allocation_granularity() as u64) as usize;
+                let mut previous = None;
+
+                if offset > 0 {
+                    let mut byte = [0u8; 1];
+
+                    match file.seek_read(&mut byte, offset - 1) {
+                        Ok(1) => previous = Some(byte[0]),
+                        Ok(_) => return Err(MapError::new(path, MapStep::Map, io::ErrorKind::UnexpectedEof.into())),
+                        Err(e) => return Err(MapError::new(path, MapStep::Map, e)),
+                    }
+                }
+
+                if file_size == 0 {
+                    return Ok(
+                        FileMapper {
+                            file_size: 0,
+                            map_handle: std::ptr::null_mut(),
+                            ptr: std::ptr::null_mut(),
+                            map_len: 0,
+                            skew: 0,
+                            offset,
+                            previous,
+                            file,
+                            stamp,
+                        }
+                    );
+                }
+
                 let map_handle = CreateFileMappingW(
                     file.as_raw_handle(),
                     std::ptr::null_mut(),
@@ -130,46 +511,177 @@ pub mod windows_map {
                     std::ptr::null(),
                 );
 
-                if map_handle == INVALID_HANDLE_VALUE {
-                    return Err("Failed to map file. CreateFileMappingW failed.");
+                //CreateFileMappingW() returns NULL on failure, not INVALID_HANDLE_VALUE
+                if map_handle.is_null() {
+                    return Err(MapError::new(path, MapStep::Map, io::Error::last_os_error()));
                 }
 
+                let view_offset = offset - skew as u64;
                 let ptr = MapViewOfFile(
                     map_handle,
                     FILE_MAP_READ,
-                    0,
-                    0,
-                    0
+                    (view_offset >> 32) as u32,
+                    view_offset as u32,
+                    file_size + skew
                 );
 
-                if ptr == std::ptr::null_mut() {
-                    return Err("Failed to get a mapped view of file. MapViewOfFile() failed.");
+                if ptr.is_null() {
+                    let e = io::Error::last_os_error();
+
+                    CloseHandle(map_handle);
+
+                    return Err(MapError::new(path, MapStep::View, e));
                 }
 
-                let file_size = GetFileSize(file.as_raw_handle(), std::ptr::null_mut()) as usize;
-    
-                Ok(
-                    FileMapper {
-                        file_size,
-                        map_handle,
-                        ptr,
-                        file, 
-                    }
-                )
+                let mapper = FileMapper {
+                    file_size,
+                    map_handle,
+                    ptr,
+                    map_len: file_size + skew,
+                    skew,
+                    offset,
+                    previous,
+                    file,
+                    stamp,
+                };
+
+                //Windows has no sequential or random access hints for mapped files
+                if options.advice == Advice::WillNeed || options.populate {
+                    mapper.advise(0..mapper.file_size, Advice::WillNeed).map_err(|e| MapError::new(path, MapStep::Advise, e))?;
+                }
+
+                if options.lock {
+                    mapper.lock(0..mapper.file_size).map_err(|e| MapError::new(path, MapStep::Lock, e))?;
+                }
+
+                Ok(mapper)
             }
         }
 
+        fn page_range(&self, range: Range<usize>) -> Option<(*mut winapi::ctypes::c_void, usize)> {
+            let end = self.skew + range.end.min(self.file_size);
+            let start = self.skew + range.start;
+            let start = start - start % page_size();
+
+            if self.ptr.is_null() || start >= end {
+                return None;
+            }
+
+            Some((unsafe { (self.ptr as *mut u8).add(start) as *mut winapi::ctypes::c_void }, end - start))
+        }
+
+        pub fn advise(&self, range: Range<usize>, advice: Advice) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if advice != Advice::WillNeed {
+                return Ok(());
+            }
+
+            let mut entry = WIN32_MEMORY_RANGE_ENTRY {
+                VirtualAddress: ptr,
+                NumberOfBytes: len,
+            };
+
+            if unsafe { PrefetchVirtualMemory(GetCurrentProcess(), 1, &mut entry, 0) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        pub fn lock(&self, range: Range<usize>) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if unsafe { VirtualLock(ptr, len) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        pub fn unlock(&self, range: Range<usize>) -> io::Result<()> {
+            let (ptr, len) = match self.page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            if unsafe { VirtualUnlock(ptr, len) } == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(())
+        }
+
+        fn inner_page_range(&self, range: Range<usize>) -> Option<(usize, usize)> {
+            let page = page_size();
+            let start = (self.skew + range.start).div_ceil(page) * page;
+            let end = if range.end >= self.file_size { self.map_len } else { (self.skew + range.end) / page * page };
+
+            if self.ptr.is_null() || start >= end {
+                return None;
+            }
+
+            Some((start, end - start))
+        }
+
+        pub fn release(&self, range: Range<usize>) -> io::Result<()> {
+            let (start, len) = match self.inner_page_range(range) {
+                Some(r) => r,
+                None => return Ok(()),
+            };
+
+            //Unlocking pages that are not locked removes them from the working set
+            unsafe {
+                VirtualUnlock((self.ptr as *mut u8).add(start) as *mut winapi::ctypes::c_void, len);
+            }
+
+            Ok(())
+        }
+
+        pub fn resident_size(&self) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Unsupported, "The resident size is not available on Windows."))
+        }
+
+        pub fn check(&self) -> io::Result<FileChange> {
+            self.stamp.compare(&self.file)
+        }
+
         pub fn get_bytes(&self) -> &[u8] {
-            unsafe {std::slice::from_raw_parts(self.ptr as *const u8, self.file_size)}
+            if self.ptr.is_null() {
+                return &[];
+            }
+
+            unsafe {std::slice::from_raw_parts((self.ptr as *const u8).add(self.skew), self.file_size)}
         }
 
         pub fn size(&self) -> usize {
             self.file_size
         }
+
+        pub fn offset(&self) -> u64 {
+            self.offset
+        }
+
+        pub fn first_record_offset(&self) -> usize {
+            crate::mmap::record_start_after(self.previous, self.get_bytes(), 0)
+        }
     }
 
-    impl <'a> Drop for FileMapper {
+    unsafe impl Send for FileMapper {}
+    unsafe impl Sync for FileMapper {}
+
+    impl Drop for FileMapper {
         fn drop(&mut self) {
+            if self.ptr.is_null() {
+                return;
+            }
+
             unsafe {
                 UnmapViewOfFile(self.ptr);
                 CloseHandle(self.map_handle);
@@ -0,0 +1,161 @@
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::Parser;
+
+use super::FileMapper;
+
+///Identifies the file at a path. A different identity means the file was replaced, such as by log rotation.
+#[cfg(unix)]
+fn file_identity(metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_identity(_metadata: &std::fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+//How many bytes before the offset are kept to tell whether the file was rewritten
+const TAIL_LEN: usize = 64;
+
+///Parses a CSV file that other processes keep appending to, like ``tail -f``.
+///
+///Every call to ``poll()`` maps the part of the file written since the last call, if it has grown, and reports the
+///records completed in it. A record is reported only after its line end has been written, so a half written last
+///line is never reported. If the file is truncated or replaced by a new file (detected by inode on Unix), parsing
+///restarts from the beginning of the file. Record indexes keep increasing across restarts.
+///
+///A truncated file that has grown past the offset again, as with ``copytruncate`` log rotation, is detected by
+///comparing the last 64 bytes already parsed with the file. A file rewritten with the same bytes there is taken to
+///be the same file.
+///
+/// # Example
+/// ```no_run
+/// let mut follower = rcsv::mmap::Follower::new("events.csv");
+/// let stop = std::sync::atomic::AtomicBool::new(false);
+///
+/// follower.follow::<10>(std::time::Duration::from_millis(500), &stop, |index, fields| {
+///     println!("Record no: {} Field count: {}", index, fields.len());
+/// }).unwrap();
+/// ```
+pub struct Follower {
+    path: PathBuf,
+    parser: Parser,
+    //Offset in the file of the first byte not yet parsed
+    offset: usize,
+    index: usize,
+    identity: Option<(u64, u64)>,
+    restarts: usize,
+    //The bytes of the file just before offset, up to TAIL_LEN of them
+    tail: Vec<u8>,
+}
+
+impl Follower {
+    ///Creates a follower of the file at ``path``. The file does not need to exist yet.
+    pub fn new<P: AsRef<Path>>(path: P) -> Follower {
+        Follower {
+            path: path.as_ref().to_path_buf(),
+            parser: Parser::new(),
+            offset: 0,
+            index: 0,
+            identity: None,
+            restarts: 0,
+            tail: Vec::with_capacity(TAIL_LEN),
+        }
+    }
+
+    ///Returns the offset in the file up to which the records have been reported.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    ///Returns the number of times parsing restarted from the beginning because the file was truncated or replaced.
+    pub fn restarts(&self) -> usize {
+        self.restarts
+    }
+
+    ///Reports the records completed since the last call. The closure is called for every record as in ``Parser::parse()``.
+    ///Returns the number of records reported. A missing file is not an error, it may be in the middle of being rotated.
+    pub fn poll<const N: usize>(&mut self, mut consumer: impl FnMut(usize, &[&[u8]])) -> io::Result<usize> {
+        let metadata = match std::fs::metadata(&self.path) {
+            Ok(m) => m,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let identity = file_identity(&metadata);
+        let size = metadata.len() as usize;
+
+        if (self.identity.is_some() && identity != self.identity) || size < self.offset {
+            self.restart();
+        }
+
+        self.identity = identity;
+
+        if size == self.offset {
+            return Ok(0);
+        }
+
+        //Map from the tail on, to check that the file still has it
+        let (mapper, from) = loop {
+            let from = self.offset - self.tail.len();
+            let mapper = match FileMapper::map_range(&self.path, from as u64, usize::MAX) {
+                Ok(m) => m,
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+                Err(e) => return Err(e.into()),
+            };
+
+            if mapper.get_bytes().starts_with(&self.tail) {
+                break (mapper, from);
+            }
+
+            if mapper.get_bytes().len() < self.tail.len() {
+                //Truncated after the size was checked. Try again next time.
+                return Ok(0);
+            }
+
+            //Truncated and written again past the offset
+            self.restart();
+        };
+        let data = mapper.get_bytes();
+
+        if self.offset == 0 {
+            self.offset = crate::encoding::utf8_bom_len(data);
+        }
+
+        let (consumed, count) = self.parser.parse_complete::<N>(&data[self.offset - from..], false, self.index, &mut consumer);
+
+        self.offset += consumed;
+        self.index += count;
+
+        if consumed > 0 {
+            let end = self.offset - from;
+
+            self.tail.clear();
+            self.tail.extend_from_slice(&data[end.saturating_sub(TAIL_LEN)..end]);
+        }
+
+        Ok(count)
+    }
+
+    fn restart(&mut self) {
+        self.offset = 0;
+        self.tail.clear();
+        self.restarts += 1;
+    }
+
+    ///Calls ``poll()`` every ``interval`` until ``stop`` is set.
+    pub fn follow<const N: usize>(&mut self, interval: Duration, stop: &AtomicBool, mut consumer: impl FnMut(usize, &[&[u8]])) -> io::Result<()> {
+        while !stop.load(Ordering::Relaxed) {
+            if self.poll::<N>(&mut consumer)? == 0 {
+                std::thread::sleep(interval);
+            }
+        }
+
+        Ok(())
+    }
+}
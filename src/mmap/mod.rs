@@ -1,9 +1,34 @@
 //!Provides a cross platform way to get the bytes in a CSV file using memory mapping. Currently Linux, macOS and Windows are supported.
 
+mod check;
+mod error;
 mod file_mapper;
+mod follow;
+mod mutable;
+mod options;
+mod parallel;
+mod range;
+mod release;
+mod resync;
+mod window;
+
+pub use check::FileChange;
+pub use error::{MapError, MapStep};
+pub use options::{Advice, MapOptions};
 
 #[cfg(unix)]
 pub use file_mapper::unix_map::FileMapper;
+#[cfg(unix)]
+pub(crate) use file_mapper::unix_map::page_size;
 
 #[cfg(windows)]
-pub use file_mapper::windows_map::FileMapper;
\ No newline at end of file
+pub use file_mapper::windows_map::FileMapper;
+#[cfg(windows)]
+pub(crate) use file_mapper::windows_map::page_size;
+
+pub use follow::Follower;
+pub use mutable::{FieldLocation, MutableFileMapper, PatchError};
+pub use range::{next_record_start, MappedRange};
+pub(crate) use range::record_start_after;
+pub use resync::{find_record_start, Certainty, RecordStart};
+pub use window::{Window, WindowedMapper};
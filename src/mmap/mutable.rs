@@ -0,0 +1,282 @@
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::{MapError, MapStep};
+
+///The location of a field in a file mapped by ``MutableFileMapper``, as returned by ``MutableFileMapper::locate()``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldLocation {
+    ///The offset of the first byte of the field in the file.
+    pub offset: usize,
+    ///The length of the field in bytes.
+    pub len: usize,
+}
+
+///The reason a field could not be overwritten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchError {
+    ///The new value does not have the same length as the field. Fields can only be overwritten in place.
+    LengthMismatch { expected: usize, actual: usize },
+    ///The location is not inside the mapped file.
+    OutOfBounds { offset: usize, len: usize },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchError::LengthMismatch { expected, actual } => write!(f, "The new value is {} bytes long but the field is {} bytes long.", actual, expected),
+            PatchError::OutOfBounds { offset, len } => write!(f, "The field at offset {} of length {} is outside the file.", offset, len),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+///Maps a file in read-write mode, so that fields can be changed in place without rewriting the file. Changes are
+///visible to other processes that map or read the file right away. They are written to the disk by ``flush()``,
+///or eventually by the operating system.
+///
+///Only a value of the same length as the field can be written, such as flipping a status column from ``N`` to ``Y``.
+///The value is written as is. It must not contain the delimiter, quotes or line ends, unless the field is quoted
+///and they are escaped, otherwise the structure of the file changes.
+///
+/// # Example
+/// ```
+/// use rcsv::mmap::MutableFileMapper;
+///
+/// let path = std::env::temp_dir().join(format!("rcsv-mutable-doc-{}.csv", std::process::id()));
+///
+/// std::fs::write(&path, "1,N\n2,N\n3,N\n").unwrap();
+///
+/// let mut mapper = MutableFileMapper::new(&path).unwrap();
+/// let mut locations = Vec::new();
+///
+/// rcsv::Parser::new().parse::<2>(mapper.get_bytes(), |_index, fields| {
+///     if fields[0] != b"2" {
+///         locations.push(mapper.locate(fields[1]).unwrap());
+///     }
+/// });
+///
+/// for location in locations {
+///     mapper.overwrite(location, b"Y").unwrap();
+/// }
+///
+/// mapper.flush().unwrap();
+///
+/// assert!(std::fs::read(&path).unwrap() == b"1,Y\n2,N\n3,Y\n");
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MutableFileMapper {
+    file_size: usize,
+    //Null for an empty file
+    ptr: *mut u8,
+    //Kept open for the lifetime of the mapping.
+    file: File,
+    #[cfg(windows)]
+    map_handle: winapi::um::winnt::HANDLE,
+}
+
+impl MutableFileMapper {
+    ///Maps the file at ``path`` in read-write mode. The file must be writable.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<MutableFileMapper, MapError> {
+        let path = path.as_ref();
+        let file = File::options().read(true).write(true).open(path).map_err(|e| MapError::new(path, MapStep::Open, e))?;
+
+        MutableFileMapper::map(file, path)
+    }
+
+    ///Maps a file that is already open for reading and writing. The path of a ``MapError`` returned by this function is empty.
+    pub fn from_file(file: File) -> Result<MutableFileMapper, MapError> {
+        MutableFileMapper::map(file, Path::new(""))
+    }
+
+    fn map(file: File, path: &Path) -> Result<MutableFileMapper, MapError> {
+        let file_size = file.metadata().map_err(|e| MapError::new(path, MapStep::Stat, e))?.len() as usize;
+        let mut mapper = MutableFileMapper {
+            file_size,
+            ptr: std::ptr::null_mut(),
+            file,
+            #[cfg(windows)]
+            map_handle: std::ptr::null_mut(),
+        };
+
+        if file_size > 0 {
+            #[cfg(unix)]
+            {
+                mapper.ptr = sys::map(&mapper.file, file_size).map_err(|e| MapError::new(path, MapStep::Map, e))?;
+            }
+
+            #[cfg(windows)]
+            {
+                let (map_handle, ptr) = sys::map(&mapper.file, file_size).map_err(|(step, e)| MapError::new(path, step, e))?;
+
+                mapper.map_handle = map_handle;
+                mapper.ptr = ptr;
+            }
+        }
+
+        Ok(mapper)
+    }
+
+    ///Returns all the data in the file.
+    pub fn get_bytes(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.ptr, self.file_size) }
+    }
+
+    ///Returns the size of the file.
+    pub fn size(&self) -> usize {
+        self.file_size
+    }
+
+    ///Returns the location of ``field`` in the file. ``field`` must be a slice of ``get_bytes()``, such as a field
+    ///reported by the parser. Returns ``None`` for any other slice.
+    pub fn locate(&self, field: &[u8]) -> Option<FieldLocation> {
+        let start = self.get_bytes().as_ptr() as usize;
+        let offset = (field.as_ptr() as usize).checked_sub(start)?;
+
+        if offset + field.len() > self.file_size {
+            return None;
+        }
+
+        Some(FieldLocation { offset, len: field.len() })
+    }
+
+    ///Overwrites the field at ``location`` with ``value``. ``value`` must have the same length as the field.
+    pub fn overwrite(&mut self, location: FieldLocation, value: &[u8]) -> Result<(), PatchError> {
+        if value.len() != location.len {
+            return Err(PatchError::LengthMismatch { expected: location.len, actual: value.len() });
+        }
+
+        if location.offset.checked_add(location.len).is_none_or(|end| end > self.file_size) {
+            return Err(PatchError::OutOfBounds { offset: location.offset, len: location.len });
+        }
+
+        if !value.is_empty() {
+            unsafe { std::ptr::copy_nonoverlapping(value.as_ptr(), self.ptr.add(location.offset), value.len()) };
+        }
+
+        Ok(())
+    }
+
+    ///Writes the changes to the disk and waits until they are written (``msync()`` or ``FlushViewOfFile()``).
+    pub fn flush(&self) -> io::Result<()> {
+        if self.ptr.is_null() {
+            return Ok(());
+        }
+
+        sys::flush(self.ptr, self.file_size, &self.file)
+    }
+}
+
+//Changes need a mutable reference. Reading from many threads is safe.
+unsafe impl Send for MutableFileMapper {}
+unsafe impl Sync for MutableFileMapper {}
+
+impl Drop for MutableFileMapper {
+    fn drop(&mut self) {
+        if self.ptr.is_null() {
+            return;
+        }
+
+        #[cfg(unix)]
+        sys::unmap(self.ptr, self.file_size);
+        #[cfg(windows)]
+        sys::unmap(self.ptr, self.map_handle);
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    pub fn map(file: &File, len: usize) -> io::Result<*mut u8> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_FILE | libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ptr as *mut u8)
+    }
+
+    pub fn flush(ptr: *mut u8, len: usize, _file: &File) -> io::Result<()> {
+        if unsafe { libc::msync(ptr as *mut libc::c_void, len, libc::MS_SYNC) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    pub fn unmap(ptr: *mut u8, len: usize) {
+        unsafe {
+            libc::munmap(ptr as *mut libc::c_void, len);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::memoryapi::{CreateFileMappingW, FlushViewOfFile, MapViewOfFile, UnmapViewOfFile, FILE_MAP_WRITE};
+    use winapi::um::winnt::{HANDLE, PAGE_READWRITE};
+
+    use crate::mmap::MapStep;
+
+    pub fn map(file: &File, len: usize) -> Result<(HANDLE, *mut u8), (MapStep, io::Error)> {
+        unsafe {
+            let map_handle = CreateFileMappingW(file.as_raw_handle(), std::ptr::null_mut(), PAGE_READWRITE, 0, 0, std::ptr::null());
+
+            if map_handle.is_null() {
+                return Err((MapStep::Map, io::Error::last_os_error()));
+            }
+
+            let ptr = MapViewOfFile(map_handle, FILE_MAP_WRITE, 0, 0, len);
+
+            if ptr.is_null() {
+                let e = io::Error::last_os_error();
+
+                CloseHandle(map_handle);
+
+                return Err((MapStep::View, e));
+            }
+
+            Ok((map_handle, ptr as *mut u8))
+        }
+    }
+
+    pub fn flush(ptr: *mut u8, len: usize, file: &File) -> io::Result<()> {
+        if unsafe { FlushViewOfFile(ptr as *const winapi::ctypes::c_void, len) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        //FlushViewOfFile() does not wait for the disk
+        file.sync_data()
+    }
+
+    pub fn unmap(ptr: *mut u8, map_handle: HANDLE) {
+        unsafe {
+            UnmapViewOfFile(ptr as *const winapi::ctypes::c_void);
+            CloseHandle(map_handle);
+        }
+    }
+}
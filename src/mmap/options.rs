@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::path::Path;
+
+use super::{FileMapper, MapError, MapStep};
+
+///How the mapped data is going to be accessed. The operating system uses this to decide how much to read ahead
+///and which pages to evict first. This is a hint, it never changes the data seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    ///No particular access pattern. The default.
+    Normal,
+    ///The data is read from the start to the end, as by ``Parser::parse()``. Read ahead aggressively and drop
+    ///pages soon after they are read.
+    Sequential,
+    ///The data is read in random order, as when looking up records by an index. Read ahead is disabled.
+    Random,
+    ///The data will be needed soon. Start reading it in the background.
+    WillNeed,
+}
+
+///Options that control how a file is memory mapped.
+///
+/// # Example
+/// ```
+/// use rcsv::mmap::{Advice, MapOptions};
+///
+/// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+/// let mapper = MapOptions::new()
+///     .advice(Advice::Sequential)
+///     .populate(true)
+///     .map(path)
+///     .unwrap();
+///
+/// rcsv::Parser::new().parse::<3>(mapper.get_bytes(), |_index, fields| {
+///     assert!(fields.len() == 3);
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MapOptions {
+    pub(crate) advice: Advice,
+    pub(crate) populate: bool,
+    pub(crate) huge_pages: bool,
+    pub(crate) lock: bool,
+    pub(crate) shared_lock: bool,
+    pub(crate) snapshot: bool,
+}
+
+impl Default for MapOptions {
+    fn default() -> Self {
+        MapOptions::new()
+    }
+}
+
+impl MapOptions {
+    ///Creates the options used by ``FileMapper::new()``. No advice is given and pages are read on first access.
+    pub fn new() -> MapOptions {
+        MapOptions {
+            advice: Advice::Normal,
+            populate: false,
+            huge_pages: false,
+            lock: false,
+            shared_lock: false,
+            snapshot: false,
+        }
+    }
+
+    ///Sets the access pattern of the whole file. It can be changed per range later with ``FileMapper::advise()``.
+    pub fn advice(mut self, advice: Advice) -> MapOptions {
+        self.advice = advice;
+
+        self
+    }
+
+    ///Reads the whole file into memory while mapping it, so that parsing does not stop at page faults.
+    ///On Linux this is ``MAP_POPULATE``. Elsewhere the pages are requested with ``Advice::WillNeed``.
+    pub fn populate(mut self, populate: bool) -> MapOptions {
+        self.populate = populate;
+
+        self
+    }
+
+    ///Asks for transparent huge pages (``MADV_HUGEPAGE``) to reduce TLB misses. Linux only and best effort.
+    ///Most kernels only back file mappings with huge pages if configured with ``CONFIG_READ_ONLY_THP_FOR_FS``.
+    ///Ignored if not supported.
+    pub fn huge_pages(mut self, huge_pages: bool) -> MapOptions {
+        self.huge_pages = huge_pages;
+
+        self
+    }
+
+    ///Locks the pages of the file in memory so that they are never paged out (``mlock()`` or ``VirtualLock()``).
+    ///Mapping fails with ``MapStep::Lock`` if the process is not allowed to lock that much memory.
+    pub fn lock(mut self, lock: bool) -> MapOptions {
+        self.lock = lock;
+
+        self
+    }
+
+    ///Takes a shared advisory lock on the file (``flock()``) for the lifetime of the mapping. Writers that take an
+    ///exclusive lock before truncating or rewriting the file wait until the mapping is dropped. Mapping waits for
+    ///such a writer to finish. The lock does not stop writers that do not lock. Unix only.
+    pub fn shared_lock(mut self, shared_lock: bool) -> MapOptions {
+        self.shared_lock = shared_lock;
+
+        self
+    }
+
+    ///Maps a private copy of the file. The file is read into anonymous ``MAP_PRIVATE`` memory while mapping, so later
+    ///changes to the file, including truncation, do not affect the mapped data and can not cause ``SIGBUS``. This costs
+    ///as much memory as the file size. A private mapping of the file itself would not do, Linux discards the private
+    ///copies of pages when the file is truncated. If the file is truncated while being read, mapping fails with
+    ///``ErrorKind::UnexpectedEof``. Unix only. Windows does not allow truncating a mapped file.
+    pub fn snapshot(mut self, snapshot: bool) -> MapOptions {
+        self.snapshot = snapshot;
+
+        self
+    }
+
+    ///Maps the file at ``path`` with these options.
+    pub fn map<P: AsRef<Path>>(&self, path: P) -> Result<FileMapper, MapError> {
+        FileMapper::with_options(path, self)
+    }
+
+    ///Maps ``len`` bytes of the file at ``path`` starting at ``offset`` with these options. See ``FileMapper::map_range()``.
+    pub fn map_range<P: AsRef<Path>>(&self, path: P, offset: u64, len: usize) -> Result<FileMapper, MapError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| MapError::new(path, MapStep::Open, e))?;
+
+        FileMapper::map(file, path, self, Some((offset, len)))
+    }
+
+    ///Maps a file that is already open with these options. See ``FileMapper::from_file()``.
+    pub fn map_file(&self, file: File) -> Result<FileMapper, MapError> {
+        FileMapper::map(file, Path::new(""), self, None)
+    }
+}
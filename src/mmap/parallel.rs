@@ -0,0 +1,275 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+use crate::scan::{self, Trace};
+use crate::{encoding, Dialect, Parser, RecordCount};
+
+use super::FileMapper;
+
+//The most bytes parsed by a thread at a time. In original order, the fields of this much data are held per thread.
+const MAX_CHUNK: usize = 4 * 1024 * 1024;
+const MIN_CHUNK: usize = 64 * 1024;
+
+///A part of the data whose records are parsed by one thread.
+struct Part {
+    range: Range<usize>,
+    //The index of the first record
+    index: usize,
+}
+
+impl Parser {
+    /// Parses the data of ``mapper`` on ``threads`` threads, or on as many threads as there are cores if ``threads``
+    /// is 0. The ``consumer`` is called from the threads at the same time, so records arrive out of order. The index
+    /// of a record is its index in the whole file, as reported by ``parse()``.
+    ///
+    /// The data is split into chunks that start after a line end. Whether a chunk starts inside a quoted field
+    /// depends on all the data before it. So each chunk is first scanned twice, once assuming it starts inside
+    /// quotes and once assuming it does not. The quoting at the start of every chunk is then known from the first
+    /// chunk, and the chunks are parsed from their first record. A record that spans chunks is parsed by the
+    /// thread of the chunk where it starts.
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    ///
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+    /// let mapper = rcsv::mmap::FileMapper::new(path).unwrap();
+    /// let fields = AtomicUsize::new(0);
+    ///
+    /// rcsv::Parser::new().par_parse::<3>(&mapper, 0, |_index, record| {
+    ///     fields.fetch_add(record.len(), Ordering::Relaxed);
+    /// });
+    ///
+    /// assert!(fields.load(Ordering::Relaxed) == 6);
+    /// ```
+    pub fn par_parse<const N: usize>(&self, mapper: &FileMapper, threads: usize, consumer: impl Fn(usize, &[&[u8]]) + Sync) {
+        let data = mapper.get_bytes();
+        let threads = thread_count(threads);
+        let parts = split(data, threads, &self.dialect);
+        let next = AtomicUsize::new(0);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.min(parts.len()) {
+                scope.spawn(|| {
+                    let mut parser = Parser::with_valid_dialect(self.dialect);
+                    let mut deliver = |index: usize, fields: &[&[u8]]| consumer(index, fields);
+
+                    while let Some(part) = parts.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        parser.parse_complete::<N>(&data[part.range.clone()], true, part.index, &mut deliver);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Parses the data of ``mapper`` on ``threads`` threads like ``par_parse()``, but the ``consumer`` is called
+    /// for one record at a time, in the original order. Each thread keeps the positions of the fields of the
+    /// chunk it has parsed until the chunks before it have been consumed. This is slower than ``par_parse()`` if
+    /// the consumer is slow, since consuming is not done in parallel.
+    ///
+    /// # Example
+    /// ```
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+    /// let mapper = rcsv::mmap::FileMapper::new(path).unwrap();
+    /// let mut indexes = Vec::new();
+    ///
+    /// rcsv::Parser::new().par_parse_ordered::<3>(&mapper, 2, |index, _fields| {
+    ///     indexes.push(index);
+    /// });
+    ///
+    /// assert!(indexes == [0, 1]);
+    /// ```
+    pub fn par_parse_ordered<const N: usize>(&self, mapper: &FileMapper, threads: usize, consumer: impl FnMut(usize, &[&[u8]]) + Send) {
+        let data = mapper.get_bytes();
+        let threads = thread_count(threads);
+        let parts = split(data, threads, &self.dialect);
+        let next = AtomicUsize::new(0);
+        //The part whose records are consumed next
+        let turn = Mutex::new((0, consumer));
+        let turn_changed = Condvar::new();
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads.min(parts.len()) {
+                scope.spawn(|| {
+                    let mut parser = Parser::with_valid_dialect(self.dialect);
+                    //The fields of the part and the number of fields of each record
+                    let mut fields: Vec<Range<usize>> = Vec::new();
+                    let mut records: Vec<usize> = Vec::new();
+                    //Wakes up the other threads if the consumer panics, so they do not wait forever
+                    let _wake = Wake(&turn_changed);
+
+                    loop {
+                        let number = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(part) = parts.get(number) else {
+                            return;
+                        };
+
+                        fields.clear();
+                        records.clear();
+
+                        parser.parse_complete::<N>(&data[part.range.clone()], true, part.index, &mut |_, record: &[&[u8]]| {
+                            for field in record {
+                                let start = field.as_ptr() as usize - data.as_ptr() as usize;
+
+                                fields.push(start..start + field.len());
+                            }
+
+                            records.push(record.len());
+                        });
+
+                        let mut guard = turn.lock().unwrap();
+
+                        while guard.0 != number {
+                            guard = turn_changed.wait(guard).unwrap();
+                        }
+
+                        let mut record: [&[u8]; N] = [&[]; N];
+                        let mut field = 0;
+
+                        for (i, &count) in records.iter().enumerate() {
+                            for slot in record.iter_mut().take(count) {
+                                *slot = &data[fields[field].clone()];
+                                field += 1;
+                            }
+
+                            (guard.1)(part.index + i, &record[0..count]);
+                        }
+
+                        guard.0 += 1;
+                        turn_changed.notify_all();
+                    }
+                });
+            }
+        });
+    }
+    /// Counts the records and lines of the data of ``mapper`` like ``count_records()``, on ``threads`` threads or
+    /// on as many threads as there are cores if ``threads`` is 0. The chunks are scanned as by ``par_parse()``.
+    ///
+    /// # Example
+    /// ```
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+    /// let mapper = rcsv::mmap::FileMapper::new(path).unwrap();
+    /// let count = rcsv::Parser::new().par_count_records(&mapper, 0);
+    ///
+    /// assert!(count == rcsv::Parser::new().count_records(mapper.get_bytes()));
+    /// ```
+    pub fn par_count_records(&self, mapper: &FileMapper, threads: usize) -> RecordCount {
+        let mut count = RecordCount::default();
+        let mut inside = false;
+
+        for chunk in trace_chunks(mapper.get_bytes(), thread_count(threads), &self.dialect, true) {
+            let trace = chunk.traces[inside as usize].unwrap();
+
+            count.records += trace.records;
+            count.lines += chunk.lines;
+            inside = trace.inside;
+        }
+
+        count
+    }
+}
+
+struct Wake<'a>(&'a Condvar);
+
+impl Drop for Wake<'_> {
+    fn drop(&mut self) {
+        self.0.notify_all();
+    }
+}
+
+fn thread_count(threads: usize) -> usize {
+    if threads > 0 {
+        return threads;
+    }
+
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+///A chunk of the data and its records, assuming it starts outside and inside quotes.
+struct Chunk {
+    start: usize,
+    traces: [Option<Trace>; 2],
+    lines: usize,
+}
+
+///Splits ``data`` into chunks and traces each both ways in parallel. The first chunk starts outside quotes.
+///The lines of the chunks are counted if ``lines`` is true.
+fn trace_chunks(data: &[u8], threads: usize, dialect: &Dialect, lines: bool) -> Vec<Chunk> {
+    let start = encoding::utf8_bom_len(data);
+    let size = (data.len() / (threads * 4)).clamp(MIN_CHUNK, MAX_CHUNK);
+    let mut bounds = vec![start];
+
+    while let Some(&last) = bounds.last() {
+        let bound = scan::line_start(data, last + size, dialect.escape);
+
+        if bound >= data.len() {
+            bounds.push(data.len());
+
+            break;
+        }
+
+        bounds.push(bound);
+    }
+
+    let mut chunks: Vec<Chunk> = bounds[..bounds.len() - 1].iter().map(|&start| Chunk { start, traces: [None; 2], lines: 0 }).collect();
+    let per_thread = chunks.len().div_ceil(threads).max(1);
+
+    std::thread::scope(|scope| {
+        for (group, chunks) in chunks.chunks_mut(per_thread).enumerate() {
+            let bounds = &bounds;
+
+            scope.spawn(move || {
+                for (i, chunk) in chunks.iter_mut().enumerate() {
+                    let number = group * per_thread + i;
+                    let data = &data[..bounds[number + 1]];
+
+                    chunk.traces[0] = Some(scan::trace(data, chunk.start, false, dialect));
+
+                    if number > 0 {
+                        chunk.traces[1] = Some(scan::trace(data, chunk.start, true, dialect));
+                    }
+
+                    if lines {
+                        chunk.lines = scan::count_lines(&data[chunk.start..]);
+                    }
+                }
+            });
+        }
+    });
+
+    chunks
+}
+
+///Splits ``data`` into parts that hold whole records and finds the index of the first record of each.
+fn split(data: &[u8], threads: usize, dialect: &Dialect) -> Vec<Part> {
+    let mut parts: Vec<Part> = Vec::new();
+    let mut inside = false;
+    let mut ended: usize = 0;
+
+    //Follow the quoting from the first chunk
+    for chunk in trace_chunks(data, threads, dialect, false) {
+        let trace = chunk.traces[inside as usize].unwrap();
+        let first = if inside {
+            trace.first_end
+        } else {
+            Some(chunk.start)
+        };
+
+        if let Some(first) = first {
+            if let Some(previous) = parts.last_mut() {
+                previous.range.end = first;
+            }
+
+            parts.push(Part {
+                range: first..data.len(),
+                index: ended + inside as usize,
+            });
+        }
+
+        ended += trace.records;
+        inside = trace.inside;
+    }
+
+    parts
+}
@@ -0,0 +1,152 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use super::FileMapper;
+
+///Returns the position of the first record of ``data`` that starts at or after ``pos``. ``data[pos - 1]`` tells whether
+///a record starts right at ``pos``. Otherwise the record starts after the next line end. Returns ``data.len()`` if no
+///record starts after ``pos``.
+///
+///This is used to split a file into parts that are parsed separately. The records of a part are those that start in it,
+///from ``next_record_start(data, start)`` up to ``next_record_start(data, end)``. Line ends inside quoted fields are not
+///told apart from those between records.
+///
+/// # Example
+/// ```
+/// let data = b"aa,bb\r\ncc,dd\nee,ff\n";
+///
+/// assert!(rcsv::mmap::next_record_start(data, 0) == 0);
+/// assert!(rcsv::mmap::next_record_start(data, 3) == 7);
+/// assert!(rcsv::mmap::next_record_start(data, 6) == 7);
+/// assert!(rcsv::mmap::next_record_start(data, 7) == 7);
+/// ```
+pub fn next_record_start(data: &[u8], pos: usize) -> usize {
+    if pos == 0 {
+        return 0;
+    }
+
+    record_start_after(data.get(pos - 1).copied(), data, pos)
+}
+
+///Returns the position of the first record that starts at or after ``pos``, given the byte before ``pos``.
+///``None`` is the start of the file.
+pub(crate) fn record_start_after(previous: Option<u8>, data: &[u8], pos: usize) -> usize {
+    let pos = pos.min(data.len());
+
+    //The parser swallows the byte after a CR, whether it is a LF or not
+    let line_end = match previous {
+        None | Some(b'\n') => return pos,
+        Some(b'\r') => pos,
+        Some(_) => match data[pos..].iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(i) if data[pos + i] == b'\r' => pos + i + 1,
+            Some(i) => return pos + i + 1,
+            None => return data.len(),
+        },
+    };
+
+    (line_end + 1).min(data.len())
+}
+
+///A view of a range of bytes of a mapped file. It holds a reference to the ``FileMapper``, so the file stays mapped
+///for as long as any view of it is alive. Views are cheap to create and clone. Since ``FileMapper`` is ``Send``
+///and ``Sync``, the views of one file can be handed to different threads.
+///
+/// # Example
+/// ```
+/// use std::sync::Arc;
+/// use rcsv::mmap::{FileMapper, MappedRange};
+///
+/// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+/// let mapper = Arc::new(FileMapper::new(path).unwrap());
+/// //Split after the first line
+/// let (first, rest) = MappedRange::from(mapper).split_at(9);
+///
+/// let worker = std::thread::spawn(move || {
+///     let mut count = 0;
+///
+///     rcsv::Parser::new().parse::<3>(rest.get_bytes(), |_, _| count += 1);
+///
+///     count
+/// });
+///
+/// assert!(first.get_bytes() == "aa,bb,cc\n".as_bytes());
+/// assert!(worker.join().unwrap() == 1);
+/// ```
+#[derive(Clone)]
+pub struct MappedRange {
+    mapper: Arc<FileMapper>,
+    offset: usize,
+    len: usize,
+}
+
+impl MappedRange {
+    ///Creates a view of the bytes in ``range`` of ``mapper.get_bytes()``. Panics if the range is out of the bounds
+    ///of the mapped data, as slicing does.
+    pub fn new(mapper: Arc<FileMapper>, range: Range<usize>) -> MappedRange {
+        //Check the bounds the way slicing does
+        let _ = &mapper.get_bytes()[range.clone()];
+
+        MappedRange {
+            mapper,
+            offset: range.start,
+            len: range.end - range.start,
+        }
+    }
+
+    ///Returns the bytes of the view.
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.mapper.get_bytes()[self.offset..self.offset + self.len]
+    }
+
+    ///Returns the offset of the view in the mapped data, ``mapper().get_bytes()``. For a mapper created with
+    ///``FileMapper::map_range()`` this is not the offset in the file: add ``mapper().offset()`` to it.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    ///Returns the number of bytes of the view.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    ///Returns true if the view has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    ///Returns the mapper of the file.
+    pub fn mapper(&self) -> &Arc<FileMapper> {
+        &self.mapper
+    }
+
+    ///Creates a view of the bytes in ``range`` of this view. The range is relative to the start of this view.
+    pub fn slice(&self, range: Range<usize>) -> MappedRange {
+        let _ = &self.get_bytes()[range.clone()];
+
+        MappedRange {
+            mapper: self.mapper.clone(),
+            offset: self.offset + range.start,
+            len: range.end - range.start,
+        }
+    }
+
+    ///Splits the view in two at ``mid``, relative to the start of this view. Panics if ``mid`` is past the end.
+    pub fn split_at(self, mid: usize) -> (MappedRange, MappedRange) {
+        (self.slice(0..mid), self.slice(mid..self.len))
+    }
+}
+
+impl From<Arc<FileMapper>> for MappedRange {
+    ///Creates a view of the whole file.
+    fn from(mapper: Arc<FileMapper>) -> Self {
+        let len = mapper.size();
+
+        MappedRange::new(mapper, 0..len)
+    }
+}
+
+impl AsRef<[u8]> for MappedRange {
+    fn as_ref(&self) -> &[u8] {
+        self.get_bytes()
+    }
+}
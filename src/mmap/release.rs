@@ -0,0 +1,67 @@
+use crate::{encoding, Error, Parser};
+
+use super::FileMapper;
+
+//The number of bytes parsed between releases
+const RELEASE_INTERVAL: usize = 8 * 1024 * 1024;
+
+impl Parser {
+    /// Parses the data of ``mapper`` like ``parse()``, releasing the pages it has passed as it goes with
+    /// ``FileMapper::release()``. Memory use stays bounded however large the file is, and the page cache is not
+    /// flooded with data that will not be read again.
+    ///
+    /// The pages of the last ``lag`` bytes before the current record are kept, for consumers that look back a little.
+    /// Data is released every few megabytes. The released data can still be read, it is read from the file again.
+    ///
+    /// # Example
+    /// ```
+    /// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+    /// let mapper = rcsv::mmap::FileMapper::new(path).unwrap();
+    /// let mut parser = rcsv::Parser::new();
+    ///
+    /// parser.parse_releasing::<3>(&mapper, 1024 * 1024, |_index, fields| {
+    ///     assert!(fields.len() == 3);
+    /// }).unwrap();
+    /// ```
+    pub fn parse_releasing<const N: usize>(&mut self, mapper: &FileMapper, lag: usize, mut consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        let data = mapper.get_bytes();
+        //Offset of the first byte not yet parsed and of the first byte not yet released
+        let mut start = encoding::utf8_bom_len(data);
+        let mut released: usize = 0;
+        let mut index: usize = 0;
+        let mut chunk = RELEASE_INTERVAL;
+        let page = super::page_size();
+        //The data of a range starts this far into its first page
+        let skew = (mapper.offset() % page as u64) as usize;
+
+        loop {
+            let end = data.len().min(start + chunk);
+            let eof = end == data.len();
+            let (consumed, count) = self.parse_complete::<N>(&data[start..end], eof, index, &mut consumer);
+
+            if eof {
+                return Ok(());
+            }
+
+            if consumed == 0 {
+                //A record longer than the chunk
+                chunk *= 2;
+
+                continue;
+            }
+
+            start += consumed;
+            index += count;
+            chunk = RELEASE_INTERVAL;
+
+            let keep = start.saturating_sub(lag);
+
+            if keep > released {
+                mapper.release(released..keep)?;
+
+                //Partial pages at the end are released next time. Pages start at the start of the mapping.
+                released = ((skew + keep) / page * page).saturating_sub(skew);
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use crate::scan::{self, Needles};
+use crate::{encoding, Dialect, Parser};
+
+use super::next_record_start;
+
+//How far before the offset the quoting is followed from, and how far after it quotes are checked
+const LOOK_BACK: usize = 64 * 1024;
+const LOOK_AHEAD: usize = 64 * 1024;
+
+///How sure ``find_record_start()`` is of its answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Certainty {
+    ///The answer is right if the data is well formed. Either the quoting was followed from the start of the data,
+    ///or only one guess of the quoting at the start of the look back agrees with RFC 4180.
+    Certain,
+    ///The data near the offset can be read both ways, or neither way is well formed. The answer assumes the
+    ///look back starts outside quotes, unless no record would start in the look ahead then.
+    Probable,
+}
+
+///The start of a record, as found by ``find_record_start()``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordStart {
+    ///The position of the first record that starts at or after the offset, or the length of the data if none does.
+    pub offset: usize,
+    pub certainty: Certainty,
+}
+
+///Finds the first record that starts at or after ``offset`` in RFC 4180 ``data``. See ``Parser::find_record_start()``.
+///
+/// # Example
+/// ```
+/// use rcsv::mmap::{find_record_start, Certainty};
+///
+/// let data = b"id,text\n1,\"a\nb\"\n2,c\n";
+/// //The line end at 12 is inside quotes
+/// let start = find_record_start(data, 11);
+///
+/// assert!(start.offset == 16);
+/// assert!(start.certainty == Certainty::Certain);
+/// ```
+pub fn find_record_start(data: &[u8], offset: usize) -> RecordStart {
+    Parser::new().find_record_start(data, offset)
+}
+
+impl Parser {
+    /// Finds the first record that starts at or after ``offset`` in ``data``, such as to parse a file from the
+    /// middle for a split, a sample or a binary search. Unlike ``next_record_start()``, line ends inside quoted
+    /// fields are told apart from those between records.
+    ///
+    /// Whether ``offset`` is inside a quoted field depends on all the data before it. Near the start of the data,
+    /// the quoting is followed from the start and the answer is certain. Otherwise the quoting is followed from a
+    /// line end up to 64 KiB before ``offset`` twice, assuming the line end is inside and outside quotes, and
+    /// checked up to 64 KiB after ``offset``. A guess is wrong if a quote opens in the middle of a field or a closing
+    /// quote is not followed by a delimiter or a line end. If only one guess is right, or both give the same answer,
+    /// the answer is certain for well formed data. Otherwise the answer is probable. If neither guess finds a record
+    /// start in the look ahead, the answer is that of ``next_record_start()``.
+    pub fn find_record_start(&self, data: &[u8], offset: usize) -> RecordStart {
+        let start = encoding::utf8_bom_len(data);
+        let offset = offset.min(data.len());
+
+        if offset <= start + LOOK_BACK {
+            //Follow the quoting from the start
+            let guess = guess(data, start, false, offset, 0, data.len(), &self.dialect);
+
+            return RecordStart {
+                offset: guess.start.unwrap_or(data.len()),
+                certainty: Certainty::Certain,
+            };
+        }
+
+        let from = scan::line_start(data, offset - LOOK_BACK, self.dialect.escape);
+        let end = data.len().min(offset.max(from) + LOOK_AHEAD);
+        let outside = guess(data, from, false, offset, end, end, &self.dialect);
+        let inside = guess(data, from, true, offset, end, end, &self.dialect);
+
+        let (chosen, certainty) = match (outside.valid, inside.valid) {
+            (true, false) => (outside, Certainty::Certain),
+            (false, true) => (inside, Certainty::Certain),
+            _ if outside.start == inside.start => (outside, Certainty::Certain),
+            //A quoted field longer than the look ahead is unlikely
+            _ if outside.start.is_none() => (inside, Certainty::Probable),
+            _ => (outside, Certainty::Probable),
+        };
+
+        match chosen.start {
+            Some(offset) => RecordStart { offset, certainty },
+            None => RecordStart {
+                offset: next_record_start(data, offset),
+                certainty: Certainty::Probable,
+            },
+        }
+    }
+}
+
+///One guess of the quoting.
+#[derive(Clone, Copy)]
+struct Guess {
+    //The first record start at or after the offset
+    start: Option<usize>,
+    //False if the data does not follow RFC 4180 under this guess
+    valid: bool,
+}
+
+///Follows the quoting of ``data`` from ``from``, assuming it is ``inside`` a quoted field or at the start of a record,
+///until a record starts at or after ``offset``. The quoting is checked up to ``check_end``. The search for a record
+///start gives up at ``search_end``.
+fn guess(data: &[u8], from: usize, inside: bool, offset: usize, check_end: usize, search_end: usize, dialect: &Dialect) -> Guess {
+    let outside_needles = Needles::outside(dialect);
+    let inside_needles = Needles::inside(dialect);
+    let separates = |b: u8| b == dialect.delimiter || b == b'\r' || b == b'\n';
+    let mut result = Guess {
+        start: (!inside && from >= offset).then_some(from),
+        valid: true,
+    };
+    let mut inside = inside;
+    let mut i = from;
+
+    loop {
+        if i >= check_end && (result.start.is_some() || i >= search_end) {
+            break;
+        }
+
+        i = scan::find(data, i, if inside { &inside_needles } else { &outside_needles });
+
+        let Some(&ch) = data.get(i) else {
+            break;
+        };
+        let checked = i < check_end;
+
+        i += 1;
+
+        if Some(ch) == dialect.escape {
+            i = (i + 1).min(data.len());
+
+            continue;
+        }
+
+        if ch == dialect.quote {
+            if !inside {
+                //A quote opens a field
+                if checked && i - 1 > from && !separates(data[i - 2]) {
+                    result.valid = false;
+                }
+
+                inside = true;
+            } else if data.get(i) == Some(&dialect.quote) {
+                i += 1;
+            } else {
+                //A quote closes a field
+                if checked && data.get(i).is_some_and(|&b| !separates(b)) {
+                    result.valid = false;
+                }
+
+                inside = false;
+            }
+
+            continue;
+        }
+
+        if ch == b'\r' {
+            i = (i + 1).min(data.len());
+        } else if ch != b'\n' {
+            continue;
+        }
+
+        if result.start.is_none() && i >= offset {
+            result.start = Some(i);
+        }
+    }
+
+    result
+}
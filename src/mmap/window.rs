@@ -0,0 +1,277 @@
+use std::fs::File;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::{encoding, Error, Parser};
+
+use super::{MapError, MapStep};
+
+///Maps fixed size windows of a file in turn, instead of the whole file at once. At most one window of
+///``window_size`` bytes is mapped per ``Window`` alive, however large the file is.
+///
+/// # Example
+/// ```
+/// let path = concat!(env!("CARGO_MANIFEST_DIR"), "/resources/test1.csv");
+/// let mapper = rcsv::mmap::WindowedMapper::new(path, 64 * 1024 * 1024).unwrap();
+/// let mut parser = rcsv::Parser::new();
+///
+/// parser.parse_windows::<3>(&mapper, |_index, fields| {
+///     assert!(fields.len() == 3);
+/// }).unwrap();
+/// ```
+pub struct WindowedMapper {
+    path: PathBuf,
+    file: File,
+    file_size: u64,
+    window_size: usize,
+    //Null for an empty file
+    #[cfg(windows)]
+    map_handle: winapi::um::winnt::HANDLE,
+}
+
+///A mapped window of a file. It is unmapped when dropped.
+pub struct Window<'a> {
+    ptr: *mut u8,
+    len: usize,
+    offset: u64,
+    _mapper: PhantomData<&'a WindowedMapper>,
+}
+
+impl WindowedMapper {
+    ///Opens the file at ``path`` for mapping ``window_size`` bytes at a time. The window size is rounded up to a
+    ///multiple of the page size (the allocation granularity on Windows) and is at least two pages.
+    pub fn new<P: AsRef<Path>>(path: P, window_size: usize) -> Result<WindowedMapper, MapError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| MapError::new(path, MapStep::Open, e))?;
+
+        WindowedMapper::open(file, path, window_size)
+    }
+
+    ///Maps windows of a file that is already open for reading. The path of a ``MapError`` returned by this function is empty.
+    pub fn from_file(file: File, window_size: usize) -> Result<WindowedMapper, MapError> {
+        WindowedMapper::open(file, Path::new(""), window_size)
+    }
+
+    fn open(file: File, path: &Path, window_size: usize) -> Result<WindowedMapper, MapError> {
+        let file_size = file.metadata().map_err(|e| MapError::new(path, MapStep::Stat, e))?.len();
+        let granularity = granularity();
+        //A window starts at the page of a record, so one page is lost to alignment
+        let window_size = window_size.div_ceil(granularity).max(2) * granularity;
+
+        #[cfg(windows)]
+        let map_handle = sys::create_mapping(&file, file_size).map_err(|e| MapError::new(path, MapStep::Map, e))?;
+
+        Ok(WindowedMapper {
+            path: path.to_path_buf(),
+            file,
+            file_size,
+            window_size,
+            #[cfg(windows)]
+            map_handle,
+        })
+    }
+
+    ///Returns the size of the file when it was opened. Data appended later is not mapped.
+    pub fn size(&self) -> u64 {
+        self.file_size
+    }
+
+    ///Returns the window size after rounding.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    ///Maps the window that contains ``offset``. The window starts at ``offset`` rounded down to the page size
+    ///and is ``window_size()`` bytes long, or less at the end of the file. Use ``Window::offset()`` to locate
+    ///``offset`` in it. The window is empty if ``offset`` is at or after the end of the file.
+    pub fn window(&self, offset: u64) -> Result<Window<'_>, MapError> {
+        let start = if offset >= self.file_size { self.file_size } else { offset - offset % granularity() as u64 };
+        let len = (self.file_size - start).min(self.window_size as u64) as usize;
+        let mut window = Window {
+            ptr: std::ptr::null_mut(),
+            len,
+            offset: start,
+            _mapper: PhantomData,
+        };
+
+        if len > 0 {
+            #[cfg(unix)]
+            let ptr = sys::map(&self.file, start, len);
+            #[cfg(windows)]
+            let ptr = sys::map(self.map_handle, start, len);
+
+            window.ptr = ptr.map_err(|e| MapError::new(&self.path, MapStep::Map, e))?;
+        }
+
+        Ok(window)
+    }
+}
+
+//The mapping handle is only used to map views and is closed on drop
+#[cfg(windows)]
+unsafe impl Send for WindowedMapper {}
+#[cfg(windows)]
+unsafe impl Sync for WindowedMapper {}
+
+impl Drop for WindowedMapper {
+    fn drop(&mut self) {
+        #[cfg(windows)]
+        sys::close_mapping(self.map_handle);
+    }
+}
+
+impl Window<'_> {
+    ///Returns the offset in the file of the first byte of the window.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    ///Returns the data of the window.
+    pub fn get_bytes(&self) -> &[u8] {
+        if self.ptr.is_null() {
+            return &[];
+        }
+
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+impl Drop for Window<'_> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            sys::unmap(self.ptr, self.len);
+        }
+    }
+}
+
+impl Parser {
+    /// Parses a file one window of ``mapper`` at a time. At most one window is mapped at any time, so the address space
+    /// used is bounded by the window size however large the file is.
+    ///
+    /// The closure is called for every record exactly as in ``parse()``. A record that spans two windows is reported
+    /// from the next window, which is mapped starting at the page where the record starts. Hence a record must fit in
+    /// a window less one page, otherwise ``Error::RecordTooLong`` is returned. A UTF-8 byte order mark is skipped.
+    pub fn parse_windows<const N: usize>(&mut self, mapper: &WindowedMapper, mut consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        //Offset in the file of the first byte not yet parsed
+        let mut start: u64 = 0;
+        let mut index: usize = 0;
+
+        loop {
+            let window = mapper.window(start)?;
+            let data = window.get_bytes();
+            let mut skip = (start - window.offset()) as usize;
+            let eof = window.offset() + data.len() as u64 == mapper.size();
+
+            if start == 0 {
+                skip = encoding::utf8_bom_len(data);
+            }
+
+            let (consumed, count) = self.parse_complete::<N>(&data[skip..], eof, index, &mut consumer);
+
+            if eof {
+                return Ok(());
+            }
+
+            if consumed == 0 {
+                return Err(Error::RecordTooLong { offset: window.offset() + skip as u64 });
+            }
+
+            start = window.offset() + (skip + consumed) as u64;
+            index += count;
+        }
+    }
+}
+
+///Returns the alignment of the offset of a mapping.
+#[cfg(unix)]
+fn granularity() -> usize {
+    super::page_size()
+}
+
+#[cfg(windows)]
+fn granularity() -> usize {
+    super::file_mapper::windows_map::allocation_granularity()
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+
+    pub fn map(file: &File, offset: u64, len: usize) -> io::Result<*mut u8> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_FILE | libc::MAP_SHARED,
+                file.as_raw_fd(),
+                offset as libc::off_t,
+            )
+        };
+
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ptr as *mut u8)
+    }
+
+    pub fn unmap(ptr: *mut u8, len: usize) {
+        unsafe {
+            libc::munmap(ptr as *mut libc::c_void, len);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::memoryapi::{CreateFileMappingW, MapViewOfFile, UnmapViewOfFile, FILE_MAP_READ};
+    use winapi::um::winnt::{HANDLE, PAGE_READONLY};
+
+    pub fn create_mapping(file: &File, file_size: u64) -> io::Result<HANDLE> {
+        //CreateFileMappingW() fails for an empty file
+        if file_size == 0 {
+            return Ok(std::ptr::null_mut());
+        }
+
+        let handle = unsafe {
+            CreateFileMappingW(file.as_raw_handle(), std::ptr::null_mut(), PAGE_READONLY, 0, 0, std::ptr::null())
+        };
+
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(handle)
+    }
+
+    pub fn close_mapping(handle: HANDLE) {
+        if !handle.is_null() {
+            unsafe {
+                CloseHandle(handle);
+            }
+        }
+    }
+
+    pub fn map(handle: HANDLE, offset: u64, len: usize) -> io::Result<*mut u8> {
+        let ptr = unsafe { MapViewOfFile(handle, FILE_MAP_READ, (offset >> 32) as u32, offset as u32, len) };
+
+        if ptr.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ptr as *mut u8)
+    }
+
+    pub fn unmap(ptr: *mut u8, _len: usize) {
+        unsafe {
+            UnmapViewOfFile(ptr as *const winapi::ctypes::c_void);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//!Parsing of floating point numbers with the Eisel-Lemire algorithm, as described in "Number Parsing at a Gigabyte
+//!per Second" by Daniel Lemire, and as done by the fast_float library and by ``str::parse()``.
+
+use std::ops::{Div, Mul, Neg};
+
+use super::table::{POWER_OF_FIVE_128, SMALLEST_POWER_OF_FIVE};
+use super::{NumberError, NumberErrorKind};
+
+//A decimal number has at most 19 digits kept in a u64
+const MAX_DIGITS: usize = 19;
+
+///The properties of ``f64`` and ``f32`` the parser needs.
+pub(super) trait Float: Copy + std::str::FromStr + Neg<Output = Self> + Mul<Output = Self> + Div<Output = Self> {
+    const MANTISSA_EXPLICIT_BITS: i32;
+    const MINIMUM_EXPONENT: i32;
+    const INFINITE_POWER: i32;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i64;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i64;
+    //Smaller numbers are zero and larger numbers are infinite
+    const SMALLEST_POWER_OF_TEN: i64;
+    const LARGEST_POWER_OF_TEN: i64;
+    //Numbers with a mantissa and a power of 10 up to these are exact, so one operation rounds them correctly
+    const MAX_EXPONENT_FAST_PATH: i64;
+    const MAX_MANTISSA_FAST_PATH: u64 = 2 << Self::MANTISSA_EXPLICIT_BITS;
+    const INFINITY: Self;
+    const NAN: Self;
+    const ZERO: Self;
+
+    fn from_u64(v: u64) -> Self;
+    fn pow10(e: i64) -> Self;
+    fn from_parts(mantissa: u64, power2: i32) -> Self;
+}
+
+impl Float for f64 {
+    const MANTISSA_EXPLICIT_BITS: i32 = 52;
+    const MINIMUM_EXPONENT: i32 = -1023;
+    const INFINITE_POWER: i32 = 0x7FF;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i64 = -4;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i64 = 23;
+    const SMALLEST_POWER_OF_TEN: i64 = -342;
+    const LARGEST_POWER_OF_TEN: i64 = 308;
+    const MAX_EXPONENT_FAST_PATH: i64 = 22;
+    const INFINITY: Self = f64::INFINITY;
+    const NAN: Self = f64::NAN;
+    const ZERO: Self = 0.0;
+
+    fn from_u64(v: u64) -> Self {
+        v as f64
+    }
+
+    fn pow10(e: i64) -> Self {
+        const TABLE: [f64; 23] = [
+            1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16, 1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+        ];
+
+        TABLE[e as usize]
+    }
+
+    fn from_parts(mantissa: u64, power2: i32) -> Self {
+        f64::from_bits(mantissa | (power2 as u64) << Self::MANTISSA_EXPLICIT_BITS)
+    }
+}
+
+impl Float for f32 {
+    const MANTISSA_EXPLICIT_BITS: i32 = 23;
+    const MINIMUM_EXPONENT: i32 = -127;
+    const INFINITE_POWER: i32 = 0xFF;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i64 = -17;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i64 = 10;
+    const SMALLEST_POWER_OF_TEN: i64 = -65;
+    const LARGEST_POWER_OF_TEN: i64 = 38;
+    const MAX_EXPONENT_FAST_PATH: i64 = 10;
+    const INFINITY: Self = f32::INFINITY;
+    const NAN: Self = f32::NAN;
+    const ZERO: Self = 0.0;
+
+    fn from_u64(v: u64) -> Self {
+        v as f32
+    }
+
+    fn pow10(e: i64) -> Self {
+        const TABLE: [f32; 11] = [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10];
+
+        TABLE[e as usize]
+    }
+
+    fn from_parts(mantissa: u64, power2: i32) -> Self {
+        f32::from_bits((mantissa | (power2 as u64) << Self::MANTISSA_EXPLICIT_BITS) as u32)
+    }
+}
+
+///A decimal number ``mantissa * 10^exponent``. If ``truncated`` is true, digits after the first 19 were dropped and
+///the number is a little larger.
+struct Decimal {
+    mantissa: u64,
+    exponent: i64,
+    truncated: bool,
+}
+
+pub(super) fn parse<F: Float>(bytes: &[u8]) -> Result<F, NumberError> {
+    let (negative, start) = match bytes.first() {
+        Some(b'-') => (true, 1),
+        Some(b'+') => (false, 1),
+        _ => (false, 0),
+    };
+
+    let value = match special::<F>(&bytes[start..]) {
+        Some(value) => value,
+        None => {
+            let decimal = parse_decimal(bytes, start)?;
+
+            to_float::<F>(&decimal).unwrap_or_else(|| slow_path(&bytes[start..]))
+        }
+    };
+
+    Ok(if negative { -value } else { value })
+}
+
+///Parses ``inf``, ``infinity`` and ``nan`` in any case.
+fn special<F: Float>(bytes: &[u8]) -> Option<F> {
+    if bytes.eq_ignore_ascii_case(b"inf") || bytes.eq_ignore_ascii_case(b"infinity") {
+        Some(F::INFINITY)
+    } else if bytes.eq_ignore_ascii_case(b"nan") {
+        Some(F::NAN)
+    } else {
+        None
+    }
+}
+
+fn parse_decimal(bytes: &[u8], start: usize) -> Result<Decimal, NumberError> {
+    let mut decimal = Decimal {
+        mantissa: 0,
+        exponent: 0,
+        truncated: false,
+    };
+    let mut digits: usize = 0;
+    let mut seen_digit = false;
+    let mut i = start;
+
+    //The integer part. Leading zeros are not counted.
+    while let Some(digit) = digit_at(bytes, i) {
+        if decimal.mantissa == 0 && digit == 0 {
+            //A leading zero
+        } else if digits < MAX_DIGITS {
+            decimal.mantissa = decimal.mantissa * 10 + digit;
+            digits += 1;
+        } else {
+            decimal.exponent += 1;
+            decimal.truncated |= digit != 0;
+        }
+
+        seen_digit = true;
+        i += 1;
+    }
+
+    //The fraction
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+
+        while let Some(digit) = digit_at(bytes, i) {
+            if decimal.mantissa == 0 && digit == 0 {
+                decimal.exponent -= 1;
+            } else if digits < MAX_DIGITS {
+                decimal.mantissa = decimal.mantissa * 10 + digit;
+                decimal.exponent -= 1;
+                digits += 1;
+            } else {
+                decimal.truncated |= digit != 0;
+            }
+
+            seen_digit = true;
+            i += 1;
+        }
+    }
+
+    if !seen_digit {
+        return Err(missing_or_invalid(bytes, i));
+    }
+
+    //The exponent
+    if matches!(bytes.get(i), Some(b'e') | Some(b'E')) {
+        i += 1;
+
+        let negative = match bytes.get(i) {
+            Some(b'-') => {
+                i += 1;
+                true
+            }
+            Some(b'+') => {
+                i += 1;
+                false
+            }
+            _ => false,
+        };
+        let mut exponent: i64 = 0;
+
+        if digit_at(bytes, i).is_none() {
+            return Err(missing_or_invalid(bytes, i));
+        }
+
+        while let Some(digit) = digit_at(bytes, i) {
+            //Larger exponents give zero or infinity anyway
+            if exponent < 0x10000 {
+                exponent = exponent * 10 + digit as i64;
+            }
+
+            i += 1;
+        }
+
+        decimal.exponent += if negative { -exponent } else { exponent };
+    }
+
+    if i < bytes.len() {
+        return Err(NumberError::new(NumberErrorKind::InvalidDigit, i));
+    }
+
+    Ok(decimal)
+}
+
+fn digit_at(bytes: &[u8], i: usize) -> Option<u64> {
+    let digit = bytes.get(i)?.wrapping_sub(b'0');
+
+    (digit <= 9).then_some(digit as u64)
+}
+
+fn missing_or_invalid(bytes: &[u8], i: usize) -> NumberError {
+    if i < bytes.len() {
+        NumberError::new(NumberErrorKind::InvalidDigit, i)
+    } else {
+        NumberError::new(NumberErrorKind::Empty, i)
+    }
+}
+
+///Converts ``decimal`` to the closest float, or returns ``None`` if the Eisel-Lemire algorithm can not decide.
+fn to_float<F: Float>(decimal: &Decimal) -> Option<F> {
+    let q = decimal.exponent;
+    let w = decimal.mantissa;
+
+    if !decimal.truncated && w <= F::MAX_MANTISSA_FAST_PATH && (-F::MAX_EXPONENT_FAST_PATH..=F::MAX_EXPONENT_FAST_PATH).contains(&q) {
+        //Both the mantissa and the power of 10 are exact
+        let value = F::from_u64(w);
+
+        return Some(if q < 0 { value / F::pow10(-q) } else { value * F::pow10(q) });
+    }
+
+    let (mantissa, power2) = compute_float::<F>(q, w)?;
+
+    if decimal.truncated {
+        //The number is between w and w + 1 times 10^q. The result is right if both round the same.
+        if compute_float::<F>(q, w + 1)? != (mantissa, power2) {
+            return None;
+        }
+    }
+
+    Some(F::from_parts(mantissa, power2))
+}
+
+///Returns the mantissa and biased binary exponent of the float closest to ``w * 10^q``, or ``None`` if it is too
+///close to halfway between two floats to tell.
+fn compute_float<F: Float>(q: i64, mut w: u64) -> Option<(u64, i32)> {
+    if w == 0 || q < F::SMALLEST_POWER_OF_TEN {
+        return Some((0, 0));
+    }
+
+    if q > F::LARGEST_POWER_OF_TEN {
+        return Some((0, F::INFINITE_POWER));
+    }
+
+    let lz = w.leading_zeros() as i32;
+
+    w <<= lz;
+
+    let (lo, hi) = compute_product_approx(q, w, F::MANTISSA_EXPLICIT_BITS + 3);
+
+    //The product may be off by one in its low bits, except for exponents where it is exact
+    if lo == u64::MAX && !(-27..=55).contains(&q) {
+        return None;
+    }
+
+    let upper_bit = (hi >> 63) as i32;
+    let shift = upper_bit + 64 - F::MANTISSA_EXPLICIT_BITS - 3;
+    let mut mantissa = hi >> shift;
+    let mut power2 = power(q as i32) + upper_bit - lz - F::MINIMUM_EXPONENT;
+
+    if power2 <= 0 {
+        //A subnormal number
+        if -power2 + 1 >= 64 {
+            return Some((0, 0));
+        }
+
+        mantissa >>= -power2 + 1;
+        mantissa += mantissa & 1;
+        mantissa >>= 1;
+        power2 = (mantissa >= (1 << F::MANTISSA_EXPLICIT_BITS)) as i32;
+
+        return Some((mantissa, power2));
+    }
+
+    //Exactly halfway between two floats rounds to even
+    if lo <= 1
+        && (F::MIN_EXPONENT_ROUND_TO_EVEN..=F::MAX_EXPONENT_ROUND_TO_EVEN).contains(&q)
+        && mantissa & 3 == 1
+        && (mantissa << shift) == hi
+    {
+        mantissa &= !1;
+    }
+
+    mantissa += mantissa & 1;
+    mantissa >>= 1;
+
+    if mantissa >= (2 << F::MANTISSA_EXPLICIT_BITS) {
+        mantissa = 1 << F::MANTISSA_EXPLICIT_BITS;
+        power2 += 1;
+    }
+
+    mantissa &= !(1 << F::MANTISSA_EXPLICIT_BITS);
+
+    if power2 >= F::INFINITE_POWER {
+        return Some((0, F::INFINITE_POWER));
+    }
+
+    Some((mantissa, power2))
+}
+
+///Returns roughly ``floor(log2(10^q)) + 63``.
+fn power(q: i32) -> i32 {
+    (q.wrapping_mul(152_170 + 65536) >> 16) + 63
+}
+
+///Multiplies ``w`` by the 128-bit power of 5 for ``q``, computing only as many low bits as ``precision`` needs.
+fn compute_product_approx(q: i64, w: u64, precision: i32) -> (u64, u64) {
+    let mask = if precision < 64 { u64::MAX >> precision } else { u64::MAX };
+    let index = 2 * (q - SMALLEST_POWER_OF_FIVE) as usize;
+    let (mut first_lo, mut first_hi) = full_multiplication(w, POWER_OF_FIVE_128[index]);
+
+    if first_hi & mask == mask {
+        let (_, second_hi) = full_multiplication(w, POWER_OF_FIVE_128[index + 1]);
+
+        first_lo = first_lo.wrapping_add(second_hi);
+
+        if second_hi > first_lo {
+            first_hi += 1;
+        }
+    }
+
+    (first_lo, first_hi)
+}
+
+fn full_multiplication(a: u64, b: u64) -> (u64, u64) {
+    let r = (a as u128) * (b as u128);
+
+    (r as u64, (r >> 64) as u64)
+}
+
+///The rare numbers the fast algorithm can not decide are parsed by the standard library. The bytes, without the
+///sign, have been checked to be a valid number, so they are ASCII.
+fn slow_path<F: Float>(bytes: &[u8]) -> F {
+    std::str::from_utf8(bytes).ok().and_then(|s| s.parse::<F>().ok()).unwrap_or(F::ZERO)
+}
@@ -0,0 +1,147 @@
+//!Parsers of numeric fields that work on the bytes of a field, without converting them to ``&str`` first.
+//!
+//!Unlike ``parse_number()``, spaces are not trimmed. A field with spaces around the number is an error, so that
+//!fields that are known to be clean are not trimmed again and again. Trim them first if needed.
+
+mod float;
+mod table;
+
+use std::fmt;
+
+///What is wrong with a numeric field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberErrorKind {
+    ///A digit is missing, as in an empty field, ``-`` or ``1e``.
+    Empty,
+    ///A byte is not part of a number.
+    InvalidDigit,
+    ///The number is too large or too small for the type.
+    Overflow,
+}
+
+///The error returned by the number parsers. It tells what is wrong and where.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberError {
+    kind: NumberErrorKind,
+    offset: usize,
+}
+
+impl NumberError {
+    fn new(kind: NumberErrorKind, offset: usize) -> NumberError {
+        NumberError { kind, offset }
+    }
+
+    ///Returns what is wrong with the field.
+    pub fn kind(&self) -> NumberErrorKind {
+        self.kind
+    }
+
+    ///Returns the offset in the field of the byte that is wrong: the invalid byte, the digit that overflows or, if
+    ///a digit is missing, where it should be. For a missing digit at the end this is the length of the field.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for NumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            NumberErrorKind::Empty => write!(f, "A digit is missing at offset {}.", self.offset),
+            NumberErrorKind::InvalidDigit => write!(f, "Invalid digit at offset {}.", self.offset),
+            NumberErrorKind::Overflow => write!(f, "The number overflows at offset {}.", self.offset),
+        }
+    }
+}
+
+impl std::error::Error for NumberError {}
+
+///Parses a field of decimal digits with an optional ``+`` or ``-`` sign into an ``i64``.
+///
+/// # Example
+/// ```
+/// use rcsv::number::{parse_i64, NumberErrorKind};
+///
+/// assert!(parse_i64(b"-42") == Ok(-42));
+/// assert!(parse_i64(b"4x2").unwrap_err().offset() == 1);
+/// assert!(parse_i64(b"9223372036854775808").unwrap_err().kind() == NumberErrorKind::Overflow);
+/// ```
+pub fn parse_i64(bytes: &[u8]) -> Result<i64, NumberError> {
+    let (negative, magnitude) = parse_signed(bytes, i64::MAX as u64)?;
+
+    //The magnitude of i64::MIN does not fit in an i64
+    Ok(if negative { (magnitude as i64).wrapping_neg() } else { magnitude as i64 })
+}
+
+///Parses a field of decimal digits with an optional ``+`` or ``-`` sign into an ``i32``.
+pub fn parse_i32(bytes: &[u8]) -> Result<i32, NumberError> {
+    let (negative, magnitude) = parse_signed(bytes, i32::MAX as u64)?;
+
+    Ok(if negative { (magnitude as i32).wrapping_neg() } else { magnitude as i32 })
+}
+
+///Parses a field of decimal digits with an optional ``+`` sign into a ``u64``.
+pub fn parse_u64(bytes: &[u8]) -> Result<u64, NumberError> {
+    let start = match bytes.first() {
+        Some(b'+') => 1,
+        _ => 0,
+    };
+
+    parse_digits(bytes, start, u64::MAX)
+}
+
+///Parses a field into an ``f64``. The field is a decimal number with an optional sign, fraction and exponent,
+///such as ``-12.5e3``, ``.5`` or ``1.``, or ``inf``, ``infinity`` or ``nan`` in any case, as accepted by
+///``str::parse()``. The result is the closest ``f64``, computed with the Eisel-Lemire algorithm. In the rare cases
+///it can not decide, ``str::parse()`` is used. Numbers too large for an ``f64`` are infinite, as for ``str::parse()``.
+///
+/// # Example
+/// ```
+/// use rcsv::number::parse_f64;
+///
+/// assert!(parse_f64(b"-12.5e3") == Ok(-12500.0));
+/// assert!(parse_f64(b"0.1") == Ok(0.1));
+/// assert!(parse_f64(b"1,5").unwrap_err().offset() == 1);
+/// ```
+pub fn parse_f64(bytes: &[u8]) -> Result<f64, NumberError> {
+    float::parse::<f64>(bytes)
+}
+
+///Parses a field into an ``f32``, like ``parse_f64()``.
+pub fn parse_f32(bytes: &[u8]) -> Result<f32, NumberError> {
+    float::parse::<f32>(bytes)
+}
+
+///Parses an optional sign and digits. The magnitude of a negative number may be one more than ``max``.
+fn parse_signed(bytes: &[u8], max: u64) -> Result<(bool, u64), NumberError> {
+    match bytes.first() {
+        Some(b'-') => Ok((true, parse_digits(bytes, 1, max + 1)?)),
+        Some(b'+') => Ok((false, parse_digits(bytes, 1, max)?)),
+        _ => Ok((false, parse_digits(bytes, 0, max)?)),
+    }
+}
+
+///Parses the digits of ``bytes`` from ``start`` into a number up to ``max``.
+fn parse_digits(bytes: &[u8], start: usize, max: u64) -> Result<u64, NumberError> {
+    if start == bytes.len() {
+        return Err(NumberError::new(NumberErrorKind::Empty, start));
+    }
+
+    let mut value: u64 = 0;
+
+    for (i, &b) in bytes.iter().enumerate().skip(start) {
+        let digit = b.wrapping_sub(b'0');
+
+        if digit > 9 {
+            return Err(NumberError::new(NumberErrorKind::InvalidDigit, i));
+        }
+
+        //value * 10 + digit <= max
+        if value > (max - digit as u64) / 10 {
+            return Err(NumberError::new(NumberErrorKind::Overflow, i));
+        }
+
+        value = value * 10 + digit as u64;
+    }
+
+    Ok(value)
+}
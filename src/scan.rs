@@ -0,0 +1,357 @@
+//!Finds the next byte that has a meaning to the parser, skipping over the ordinary bytes of a field a block at a time.
+//!The blocks are scanned with AVX2 or SSE2 on x86, picked once at run time, and with NEON on ARM. Elsewhere, and for
+//!the last few bytes, 8 bytes are checked at a time in a ``u64``.
+
+use std::sync::OnceLock;
+
+use crate::Dialect;
+
+const LOW: u64 = 0x0101_0101_0101_0101;
+const HIGH: u64 = 0x8080_8080_8080_8080;
+
+///Scans ``data`` from a position at least ``MIN_BLOCK`` bytes before its end, like ``find()``.
+type BlockScan = fn(&[u8], usize, &Needles) -> usize;
+
+///The bytes the parser stops at. Unused slots repeat one of the others. Built once per parser, since the parser
+///looks for the next one at every field.
+#[derive(Clone, Copy)]
+pub(crate) struct Needles {
+    bytes: [u8; 5],
+    //Each byte repeated in every byte of a u64, for find_words()
+    words: [u64; 5],
+    //The fastest block scan of this CPU
+    scan_blocks: BlockScan,
+}
+
+impl Needles {
+    fn new(bytes: [u8; 5]) -> Needles {
+        Needles {
+            bytes,
+            words: bytes.map(|b| LOW * b as u64),
+            scan_blocks: block_scan(),
+        }
+    }
+
+    ///The bytes that matter outside a quoted field: the delimiter, the quote, the escape and the line ends.
+    pub(crate) fn outside(dialect: &Dialect) -> Needles {
+        let escape = dialect.escape.unwrap_or(dialect.quote);
+
+        Needles::new([dialect.delimiter, dialect.quote, escape, b'\r', b'\n'])
+    }
+
+    ///The bytes that matter inside a quoted field: the quote and the escape.
+    pub(crate) fn inside(dialect: &Dialect) -> Needles {
+        let quote = dialect.quote;
+        let escape = dialect.escape.unwrap_or(quote);
+
+        Needles::new([quote, escape, quote, quote, quote])
+    }
+
+    fn contains(&self, b: u8) -> bool {
+        self.bytes.contains(&b)
+    }
+}
+
+//Shorter data is not worth a vector
+const MIN_BLOCK: usize = 16;
+
+///Returns the block scan of this CPU. The CPU features are only detected the first time.
+fn block_scan() -> BlockScan {
+    static SCAN: OnceLock<BlockScan> = OnceLock::new();
+
+    *SCAN.get_or_init(detect_block_scan)
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn detect_block_scan() -> BlockScan {
+    if is_x86_feature_detected!("avx2") {
+        x86::avx2
+    } else if is_x86_feature_detected!("sse2") {
+        x86::sse2
+    } else {
+        find_words
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+fn detect_block_scan() -> BlockScan {
+    neon::find
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64", all(target_arch = "aarch64", target_feature = "neon"))))]
+fn detect_block_scan() -> BlockScan {
+    find_words
+}
+
+///Returns the position of the first byte of ``data`` at or after ``from`` that is one of ``needles``, or ``data.len()``.
+pub(crate) fn find(data: &[u8], from: usize, needles: &Needles) -> usize {
+    if from + MIN_BLOCK <= data.len() {
+        return (needles.scan_blocks)(data, from, needles);
+    }
+
+    find_words(data, from, needles)
+}
+
+///Checks 8 bytes at a time in a ``u64`` (SWAR, SIMD within a register).
+fn find_words(data: &[u8], mut i: usize, needles: &Needles) -> usize {
+    while i + 8 <= data.len() {
+        let word = u64::from_le_bytes(data[i..i + 8].try_into().unwrap());
+        let mut mask = 0;
+
+        for v in needles.words {
+            //The high bit of each byte that is zero is set. A borrow can also set the bit of a byte above
+            //a zero byte, so only the lowest bit is exact.
+            let x = word ^ v;
+
+            mask |= x.wrapping_sub(LOW) & !x & HIGH;
+        }
+
+        if mask != 0 {
+            return i + (mask.trailing_zeros() / 8) as usize;
+        }
+
+        i += 8;
+    }
+
+    find_bytes(data, i, needles)
+}
+
+///Checks one byte at a time.
+fn find_bytes(data: &[u8], from: usize, needles: &Needles) -> usize {
+    match data[from..].iter().position(|&b| needles.contains(b)) {
+        Some(i) => from + i,
+        None => data.len(),
+    }
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    use super::{find_words, Needles};
+
+    pub fn avx2(data: &[u8], from: usize, needles: &Needles) -> usize {
+        //Only picked by detect_block_scan() when the CPU has AVX2
+        unsafe { find_avx2(data, from, needles) }
+    }
+
+    pub fn sse2(data: &[u8], from: usize, needles: &Needles) -> usize {
+        //Only picked by detect_block_scan() when the CPU has SSE2
+        unsafe { find_sse2(data, from, needles) }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn find_avx2(data: &[u8], mut i: usize, needles: &Needles) -> usize {
+        let n = needles.bytes.map(|b| _mm256_set1_epi8(b as i8));
+        let matches = |block: __m256i| {
+            let mut m = _mm256_cmpeq_epi8(block, n[0]);
+
+            for v in &n[1..] {
+                m = _mm256_or_si256(m, _mm256_cmpeq_epi8(block, *v));
+            }
+
+            _mm256_movemask_epi8(m) as u32
+        };
+
+        //64 bytes at a time
+        while i + 64 <= data.len() {
+            let ptr = data.as_ptr().add(i);
+            let low = matches(_mm256_loadu_si256(ptr as *const __m256i)) as u64;
+            let high = matches(_mm256_loadu_si256(ptr.add(32) as *const __m256i)) as u64;
+            let mask = low | (high << 32);
+
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+
+            i += 64;
+        }
+
+        if i + 32 <= data.len() {
+            let mask = matches(_mm256_loadu_si256(data.as_ptr().add(i) as *const __m256i));
+
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+
+            i += 32;
+        }
+
+        if i + 16 <= data.len() {
+            return find_sse2(data, i, needles);
+        }
+
+        find_words(data, i, needles)
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn find_sse2(data: &[u8], mut i: usize, needles: &Needles) -> usize {
+        let n = needles.bytes.map(|b| _mm_set1_epi8(b as i8));
+
+        while i + 16 <= data.len() {
+            let block = _mm_loadu_si128(data.as_ptr().add(i) as *const __m128i);
+            let mut m = _mm_cmpeq_epi8(block, n[0]);
+
+            for v in &n[1..] {
+                m = _mm_or_si128(m, _mm_cmpeq_epi8(block, *v));
+            }
+
+            let mask = _mm_movemask_epi8(m) as u32;
+
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+
+            i += 16;
+        }
+
+        find_words(data, i, needles)
+    }
+}
+
+#[cfg(all(target_arch = "aarch64", target_feature = "neon"))]
+mod neon {
+    use std::arch::aarch64::*;
+
+    use super::{find_words, Needles};
+
+    pub fn find(data: &[u8], i: usize, needles: &Needles) -> usize {
+        //NEON is enabled at compile time
+        unsafe { find_neon(data, i, needles) }
+    }
+
+    unsafe fn find_neon(data: &[u8], mut i: usize, needles: &Needles) -> usize {
+        let n = needles.bytes.map(|b| vdupq_n_u8(b));
+
+        while i + 16 <= data.len() {
+            let block = vld1q_u8(data.as_ptr().add(i));
+            let mut m = vceqq_u8(block, n[0]);
+
+            for v in &n[1..] {
+                m = vorrq_u8(m, vceqq_u8(block, *v));
+            }
+
+            if vmaxvq_u8(m) != 0 {
+                //Narrow each byte of the mask to 4 bits, there is no movemask
+                let nibbles = vshrn_n_u16::<4>(vreinterpretq_u16_u8(m));
+                let mask = vget_lane_u64::<0>(vreinterpret_u64_u8(nibbles));
+
+                return i + (mask.trailing_zeros() / 4) as usize;
+            }
+
+            i += 16;
+        }
+
+        find_words(data, i, needles)
+    }
+}
+
+///Returns the number of line ends in ``data``: LF, CR LF and a CR on its own.
+pub(crate) fn count_lines(data: &[u8]) -> usize {
+    let lf = data.iter().filter(|&&b| b == b'\n').count();
+    let cr = Needles::new([b'\r'; 5]);
+    let mut lone_cr: usize = 0;
+    let mut i = find(data, 0, &cr);
+
+    while i < data.len() {
+        if data.get(i + 1) != Some(&b'\n') {
+            lone_cr += 1;
+        }
+
+        i = find(data, i + 1, &cr);
+    }
+
+    lf + lone_cr
+}
+
+///Returns the position after the first LF at or after ``pos``, or ``data.len()``. A record starts there, unless
+///the position is inside a quoted field. A LF right after an escape character is skipped since it may be escaped.
+pub(crate) fn line_start(data: &[u8], pos: usize, escape: Option<u8>) -> usize {
+    let mut pos = pos;
+
+    while pos < data.len() {
+        match data[pos..].iter().position(|&b| b == b'\n') {
+            Some(i) if pos + i > 0 && Some(data[pos + i - 1]) == escape => pos += i + 1,
+            Some(i) => return pos + i + 1,
+            None => break,
+        }
+    }
+
+    data.len()
+}
+
+///The records found by ``trace()``.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Trace {
+    ///The number of record ends.
+    pub records: usize,
+    ///The position after the first record end.
+    pub first_end: Option<usize>,
+    ///True if the data ends inside a quoted field.
+    pub inside: bool,
+}
+
+///Counts the records of ``data`` from ``from`` that end. See ``follow()``.
+pub(crate) fn trace(data: &[u8], from: usize, inside: bool, dialect: &Dialect) -> Trace {
+    let mut records: usize = 0;
+    let mut first_end = None;
+    let inside = follow(data, from, inside, dialect, |end| {
+        records += 1;
+        first_end.get_or_insert(end);
+    });
+
+    Trace { records, first_end, inside }
+}
+
+///Follows the quoting of ``data`` from ``from`` the way ``Parser::next_field()`` does, without splitting fields, and
+///calls ``on_end`` with the position after each record end. ``inside`` tells whether ``from`` is inside a quoted
+///field. ``from`` must not be in the middle of an escape sequence or between a CR and the byte it swallows.
+///Returns true if the data ends inside a quoted field.
+pub(crate) fn follow(data: &[u8], from: usize, inside: bool, dialect: &Dialect, mut on_end: impl FnMut(usize)) -> bool {
+    let outside_needles = Needles::outside(dialect);
+    let inside_needles = Needles::inside(dialect);
+    let mut inside = inside;
+    let mut i = from;
+
+    loop {
+        i = find(data, i, if inside { &inside_needles } else { &outside_needles });
+
+        let Some(&ch) = data.get(i) else {
+            return inside;
+        };
+
+        i += 1;
+
+        if Some(ch) == dialect.escape {
+            //The next character is taken literally
+            i = (i + 1).min(data.len());
+
+            continue;
+        }
+
+        if ch == dialect.quote {
+            if !inside {
+                inside = true;
+            } else if data.get(i) == Some(&dialect.quote) {
+                i += 1;
+            } else {
+                inside = false;
+            }
+
+            continue;
+        }
+
+        if ch == b'\r' {
+            //The byte after a CR is swallowed
+            i = (i + 1).min(data.len());
+        } else if ch != b'\n' {
+            //A delimiter
+            continue;
+        }
+
+        on_end(i);
+    }
+}
@@ -0,0 +1,364 @@
+//!Guessing the dialect of CSV data of unknown origin.
+//!
+//!``sniff()`` looks at a sample from the start of the data, usually the first ``SAMPLE_SIZE`` bytes of a memory
+//!mapped file. It tries the common delimiters and quote characters and picks the dialect that splits the sample
+//!into records with the most consistent number of fields.
+//!
+//! # Example
+//! ```
+//! let data = "id;name;amount\r\n1;\"Smith; John\";10.5\r\n2;Jones;7\r\n";
+//! let result = rcsv::sniff::sniff(data.as_bytes());
+//!
+//! assert!(result.dialect.delimiter == b';');
+//! assert!(result.has_header);
+//! assert!(result.columns == 3);
+//!
+//! let mut parser = result.parser().unwrap();
+//!
+//! parser.parse::<3>(data.as_bytes(), |index, fields| {
+//!     if index == 1 {
+//!         assert!(fields[1] == "Smith; John".as_bytes());
+//!     }
+//! });
+//! ```
+
+use std::io::Read;
+
+use crate::encoding::{self, Endian, Utf16Decoder};
+use crate::{Dialect, ParseStatus, Parser};
+
+///The number of bytes at the start of the data that ``sniff()`` looks at.
+pub const SAMPLE_SIZE: usize = 64 * 1024;
+
+//The number of records of the sample that are examined for each candidate dialect.
+const MAX_RECORDS: usize = 200;
+const DELIMITERS: [u8; 5] = [b',', b';', b'\t', b'|', b':'];
+const QUOTES: [u8; 2] = [b'"', b'\''];
+
+///The line end used by the data. ``Parser`` supports ``CrLf`` and ``Lf``, but not ``Cr``.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    CrLf,
+    Lf,
+    Cr,
+}
+
+///The character encoding of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    ///Pure ASCII. Also valid UTF-8 and any of the single byte encodings.
+    Ascii,
+    ///UTF-8 without a byte order mark.
+    Utf8,
+    ///UTF-8 with a byte order mark.
+    Utf8Bom,
+    ///UTF-16 little endian, with or without a byte order mark.
+    Utf16Le,
+    ///UTF-16 big endian, with or without a byte order mark.
+    Utf16Be,
+    ///Not valid UTF-8. Probably a single byte encoding such as Windows-1252. See ``encoding::Encoding``.
+    SingleByte,
+}
+
+///The dialect and other properties of CSV data guessed by ``sniff()``.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SniffResult {
+    ///The delimiter, quote and escape characters.
+    pub dialect: Dialect,
+    ///The most common line end.
+    pub terminator: Terminator,
+    ///True if the first record looks like a header.
+    pub has_header: bool,
+    ///The character encoding.
+    pub encoding: TextEncoding,
+    ///The most common number of fields per record.
+    pub columns: usize,
+    ///How sure the guess is, from 0.0 to 1.0. It is mostly the fraction of records that have ``columns`` fields.
+    pub confidence: f64,
+}
+
+impl SniffResult {
+    ///Creates a parser configured with the guessed dialect. UTF-16 data still needs to be transcoded: wrap it in
+    ///an ``encoding::Utf16Decoder`` with the ``Endian`` of ``encoding`` and parse it with ``Parser::parse_reader()``.
+    ///``Parser::parse_auto()`` is not enough, it only detects UTF-16 that starts with a byte order mark. Returns
+    ///``None`` if the line end is ``Terminator::Cr``. The parser does not support a lone CR as a line end, it takes
+    ///the byte after a CR to be a LF and drops it.
+    pub fn parser(&self) -> Option<Parser> {
+        match self.terminator {
+            Terminator::Cr => None,
+            _ => Some(Parser::with_valid_dialect(self.dialect)),
+        }
+    }
+}
+
+///A dialect tried by ``sniff()`` and the records of the sample split with it.
+struct Candidate<'a> {
+    score: f64,
+    dialect: Dialect,
+    records: Vec<Vec<&'a [u8]>>,
+    columns: usize,
+}
+
+///Guesses the dialect, line end, header presence, encoding and number of columns of CSV data from a sample of it.
+///Only the first ``SAMPLE_SIZE`` bytes of ``sample`` are examined. The sample may end in the middle of a record.
+pub fn sniff(sample: &[u8]) -> SniffResult {
+    let sample = &sample[0..sample.len().min(SAMPLE_SIZE)];
+    let (encoding, transcoded) = detect_encoding(sample);
+    let text: &[u8] = match &transcoded {
+        Some(t) => t,
+        None => &sample[encoding::utf8_bom_len(sample)..],
+    };
+
+    let mut best: Option<Candidate> = None;
+
+    for quote in QUOTES {
+        let escape = detect_escape(text, quote);
+
+        for delimiter in DELIMITERS {
+            let dialect = Dialect { delimiter, quote, escape };
+            let records = parse_sample(text, dialect);
+
+            if records.is_empty() {
+                continue;
+            }
+
+            let (columns, frequency) = mode(&records);
+            //Consistency matters most. Fields cut in the middle of a quoted value count against the dialect.
+            //More columns break the ties.
+            let score = frequency - stray_quotes(&records, quote) * 0.5 + columns.min(20) as f64 * 0.001;
+
+            if best.as_ref().is_none_or(|b| score > b.score) {
+                best = Some(Candidate { score, dialect, records, columns });
+            }
+        }
+    }
+
+    let terminator = detect_terminator(text);
+
+    match best {
+        None => SniffResult {
+            dialect: Dialect::default(),
+            terminator,
+            has_header: false,
+            encoding,
+            columns: 0,
+            confidence: 0.0,
+        },
+        Some(Candidate { dialect, records, columns, .. }) => {
+            let (_, frequency) = mode(&records);
+            let mut confidence = frequency;
+
+            //Few records or a single column say little about the delimiter
+            if records.len() < 5 {
+                confidence *= records.len() as f64 / 5.0;
+            }
+
+            if columns < 2 {
+                confidence *= 0.5;
+            }
+
+            SniffResult {
+                dialect,
+                terminator,
+                has_header: detect_header(&records, columns),
+                encoding,
+                columns,
+                confidence,
+            }
+        }
+    }
+}
+
+///Returns the encoding of ``sample`` and, for UTF-16, the sample transcoded to UTF-8.
+fn detect_encoding(sample: &[u8]) -> (TextEncoding, Option<Vec<u8>>) {
+    let (endian, bom_len) = match encoding::utf16_bom(sample) {
+        Some(endian) => (Some(endian), 2),
+        None => (guess_utf16(sample), 0),
+    };
+
+    if let Some(endian) = endian {
+        let mut decoder = Utf16Decoder::new(&sample[bom_len..], endian);
+        let mut transcoded = Vec::with_capacity(sample.len());
+        let mut buf = [0u8; 4096];
+
+        //Stop at the first error. It is likely due to the sample being cut in the middle of a character.
+        while let Ok(n @ 1..) = decoder.read(&mut buf) {
+            transcoded.extend_from_slice(&buf[0..n]);
+        }
+
+        let encoding = match endian {
+            Endian::Little => TextEncoding::Utf16Le,
+            Endian::Big => TextEncoding::Utf16Be,
+        };
+
+        return (encoding, Some(transcoded));
+    }
+
+    if encoding::utf8_bom_len(sample) > 0 {
+        return (TextEncoding::Utf8Bom, None);
+    }
+
+    if sample.is_ascii() {
+        return (TextEncoding::Ascii, None);
+    }
+
+    match std::str::from_utf8(sample) {
+        Ok(_) => (TextEncoding::Utf8, None),
+        //A sequence cut off at the end of the sample
+        Err(e) if e.error_len().is_none() => (TextEncoding::Utf8, None),
+        Err(_) => (TextEncoding::SingleByte, None),
+    }
+}
+
+///Guesses UTF-16 without a byte order mark from the zero bytes of ASCII characters.
+fn guess_utf16(sample: &[u8]) -> Option<Endian> {
+    let sample = &sample[0..sample.len().min(1024) & !1];
+
+    if sample.len() < 4 {
+        return None;
+    }
+
+    let even_zeros = sample.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = sample.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let units = sample.len() / 2;
+
+    if odd_zeros * 10 > units * 3 && even_zeros * 10 < units {
+        Some(Endian::Little)
+    } else if even_zeros * 10 > units * 3 && odd_zeros * 10 < units {
+        Some(Endian::Big)
+    } else {
+        None
+    }
+}
+
+///Detects backslash escapes, as in ``"say \"hi\""``, as opposed to doubled quotes.
+fn detect_escape(text: &[u8], quote: u8) -> Option<u8> {
+    let backslashed = text.windows(2).filter(|w| w[0] == b'\\' && w[1] == quote).count();
+    //Doubled quotes next to text. An empty escaped field ("") is not counted.
+    let doubled = text.windows(4).filter(|w| {
+        w[1] == quote && w[2] == quote && w[0] != quote && w[3] != quote
+            && (w[0].is_ascii_alphanumeric() || w[3].is_ascii_alphanumeric() || w[0] == b' ' || w[3] == b' ')
+    }).count();
+
+    if backslashed > 0 && backslashed >= doubled {
+        Some(b'\\')
+    } else {
+        None
+    }
+}
+
+fn detect_terminator(text: &[u8]) -> Terminator {
+    let mut crlf = 0;
+    let mut lf = 0;
+    let mut cr = 0;
+
+    for i in 0..text.len() {
+        match text[i] {
+            b'\r' if text.get(i + 1) == Some(&b'\n') => crlf += 1,
+            b'\r' => cr += 1,
+            b'\n' if i == 0 || text[i - 1] != b'\r' => lf += 1,
+            _ => {}
+        }
+    }
+
+    if lf > crlf && lf >= cr {
+        Terminator::Lf
+    } else if cr > crlf && cr > lf {
+        Terminator::Cr
+    } else {
+        Terminator::CrLf
+    }
+}
+
+///Splits the complete records at the start of ``text`` into fields.
+fn parse_sample(text: &[u8], dialect: Dialect) -> Vec<Vec<&[u8]>> {
+    let mut parser = Parser::with_valid_dialect(dialect);
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+
+    while records.len() < MAX_RECORDS {
+        let status = parser.next_field(text);
+
+        match status {
+            ParseStatus::HasMoreFields => fields.push(parser.field(text)),
+            ParseStatus::EndRecord => {
+                fields.push(parser.field(text));
+                records.push(std::mem::take(&mut fields));
+            }
+            ParseStatus::EndDocument => break,
+        }
+    }
+
+    records
+}
+
+///Returns the most common number of fields and the fraction of records that have it.
+fn mode(records: &[Vec<&[u8]>]) -> (usize, f64) {
+    let mut counts: Vec<(usize, usize)> = Vec::new();
+
+    for record in records {
+        match counts.iter_mut().find(|(len, _)| *len == record.len()) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((record.len(), 1)),
+        }
+    }
+
+    //The first of equally common lengths wins
+    let (columns, count) = counts.iter().fold((0, 0), |best, &c| if c.1 > best.1 { c } else { best });
+
+    (columns, count as f64 / records.len() as f64)
+}
+
+///Returns the fraction of fields that start with a quote character other than ``quote`` but do not end with it.
+///Such fields are likely pieces of a quoted value that was split at a delimiter inside it.
+fn stray_quotes(records: &[Vec<&[u8]>], quote: u8) -> f64 {
+    let mut total = 0;
+    let mut stray = 0;
+
+    for field in records.iter().flatten() {
+        total += 1;
+
+        if let Some(&first) = field.first() {
+            if first != quote && QUOTES.contains(&first) && (field.len() < 2 || field.last() != Some(&first)) {
+                stray += 1;
+            }
+        }
+    }
+
+    if total == 0 { 0.0 } else { stray as f64 / total as f64 }
+}
+
+fn is_number(field: &[u8]) -> bool {
+    std::str::from_utf8(field).is_ok_and(|s| s.trim().parse::<f64>().is_ok())
+}
+
+///Votes on each column whether the first record differs from the rest, in type or in length, the way a header would.
+fn detect_header(records: &[Vec<&[u8]>], columns: usize) -> bool {
+    if records.len() < 2 {
+        return false;
+    }
+
+    let mut votes: i32 = 0;
+
+    for column in 0..columns {
+        let header = match records[0].get(column) {
+            Some(h) => *h,
+            None => continue,
+        };
+        let values: Vec<&[u8]> = records[1..].iter().filter_map(|r| r.get(column).copied()).filter(|v| !v.is_empty()).collect();
+
+        if values.is_empty() {
+            continue;
+        }
+
+        if values.iter().all(|v| is_number(v)) {
+            votes += if is_number(header) { -1 } else { 1 };
+        } else if values.iter().all(|v| v.len() == values[0].len()) {
+            votes += if header.len() != values[0].len() { 1 } else { -1 };
+        } else if is_number(header) {
+            votes -= 1;
+        }
+    }
+
+    votes > 0
+}
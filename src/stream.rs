@@ -0,0 +1,141 @@
+//!Parsing of CSV data that is read from a stream, such as a socket or a decompressor, in constant memory.
+//!
+//!The data is read into a buffer supplied by the caller. Complete records are reported as soon as they are
+//!in the buffer. An incomplete record at the end of the buffer is moved to the front and completed by
+//!the next read. Hence a record must fit in the buffer.
+
+use std::io::{ErrorKind, Read};
+
+use crate::encoding::{self, Utf16Decoder};
+use crate::{Error, Parser};
+
+impl Parser {
+    /// Parses CSV data read from ``reader``. The data is read into ``buffer`` and no other memory is allocated
+    /// for the input. The largest record in the data must fit in ``buffer``, otherwise ``Error::RecordTooLong`` is returned.
+    ///
+    /// The closure is called for every record exactly as in ``parse()``. As with ``parse()``, an incomplete
+    /// record at the end of the data is not reported.
+    ///
+    /// If the ``gzip`` feature is enabled, gzip compressed data is detected by its magic bytes and
+    /// decompressed on the fly. Data that starts with a UTF-16 byte order mark is transcoded to UTF-8
+    /// on the fly. A UTF-8 byte order mark is skipped.
+    ///
+    /// # Example
+    /// ```
+    /// let data = "aa,bb\r\ncc,dd\r\n";
+    /// let mut buffer = [0u8; 1024];
+    /// let mut parser = rcsv::Parser::new();
+    /// let mut count = 0;
+    ///
+    /// parser.parse_reader::<2, _>(data.as_bytes(), &mut buffer, |_index, fields| {
+    ///     assert!(fields.len() == 2);
+    ///
+    ///     count += 1;
+    /// }).unwrap();
+    ///
+    /// assert!(count == 2);
+    /// ```
+    pub fn parse_reader<const N: usize, R: Read>(&mut self, mut reader: R, buffer: &mut [u8], consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        let mut magic = [0u8; 2];
+        let magic_len = read_up_to(&mut reader, &mut magic)?;
+        let head = &magic[0..magic_len];
+
+        #[cfg(feature = "gzip")]
+        if crate::gzip::is_gzip(head) {
+            return self.parse_stream::<N, _>(crate::gzip::GzDecoder::new(head.chain(reader)), buffer, consumer);
+        }
+
+        if let Some(endian) = encoding::utf16_bom(head) {
+            return self.parse_stream::<N, _>(Utf16Decoder::new(reader, endian).with_offset(2), buffer, consumer);
+        }
+
+        self.parse_stream::<N, _>(head.chain(reader), buffer, consumer)
+    }
+
+    /// Parses in-memory CSV ``data`` that may be compressed, such as the bytes of a memory mapped file.
+    /// Uncompressed data is parsed in place by ``parse()`` and ``buffer`` is not used. Compressed data is decompressed
+    /// into ``buffer`` a piece at a time as in ``parse_reader()``.
+    ///
+    /// Compressed data is detected by its magic bytes. Currently gzip is supported if the ``gzip`` feature is enabled.
+    /// UTF-16 data with a byte order mark is also transcoded to UTF-8 into ``buffer``.
+    pub fn parse_auto<const N: usize>(&mut self, data: &[u8], buffer: &mut [u8], consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        #[cfg(feature = "gzip")]
+        if crate::gzip::is_gzip(data) {
+            return self.parse_stream::<N, _>(crate::gzip::GzDecoder::new(data), buffer, consumer);
+        }
+
+        if let Some(endian) = encoding::utf16_bom(data) {
+            return self.parse_stream::<N, _>(Utf16Decoder::new(&data[2..], endian).with_offset(2), buffer, consumer);
+        }
+
+        self.parse::<N>(data, consumer);
+
+        Ok(())
+    }
+
+    pub(crate) fn parse_stream<const N: usize, R: Read>(&mut self, mut reader: R, buffer: &mut [u8], mut consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        //Offset of the start of the buffer in the input
+        let mut offset: u64 = 0;
+        let mut filled: usize = 0;
+        let mut index: usize = 0;
+        let mut bom_checked = false;
+
+        loop {
+            if filled == buffer.len() {
+                return Err(Error::RecordTooLong { offset });
+            }
+
+            let n = match reader.read(&mut buffer[filled..]) {
+                Ok(n) => n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let eof = n == 0;
+
+            filled += n;
+
+            //Skip the UTF-8 byte order mark once enough data is read to tell
+            if !bom_checked {
+                if filled < encoding::UTF8_BOM.len() && !eof {
+                    continue;
+                }
+
+                let bom_len = encoding::utf8_bom_len(&buffer[0..filled]);
+
+                buffer.copy_within(bom_len..filled, 0);
+
+                filled -= bom_len;
+                offset += bom_len as u64;
+                bom_checked = true;
+            }
+
+            let (consumed, count) = self.parse_complete::<N>(&buffer[0..filled], eof, index, &mut consumer);
+
+            if eof {
+                return Ok(());
+            }
+
+            buffer.copy_within(consumed..filled, 0);
+
+            filled -= consumed;
+            offset += consumed as u64;
+            index += count;
+        }
+    }
+}
+
+///Reads until ``buf`` is full or the end of the stream is reached. Returns the number of bytes read.
+pub(crate) fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(filled)
+}
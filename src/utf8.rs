@@ -0,0 +1,166 @@
+//!Parsing of CSV data into ``&str`` fields.
+//!
+//!The data is validated as UTF-8 once, either per record by ``Parser::parse_str()`` or for the whole input by
+//!``validate()``. The fields are then handed out as ``&str`` without validating each one. This is sound since a
+//!field starts and ends next to a delimiter, quote, escape or line end, or at the start of a record. These are ASCII,
+//!``Dialect::validate()`` rejects any other character, and can not be a part of a multi-byte sequence. A record
+//!does not start next to an ASCII character if a lone CR ends the record before it: the parser skips the byte after
+//!a CR, which may be the first byte of a multi-byte sequence. Such a record is reported as invalid UTF-8.
+
+use std::fmt;
+
+use crate::Parser;
+
+///The data is not valid UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utf8Error {
+    ///The offset in the data of the first byte of the invalid sequence.
+    pub offset: usize,
+    ///The index of the record that contains the invalid sequence, if known.
+    pub record: Option<usize>,
+}
+
+impl fmt::Display for Utf8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.record {
+            Some(record) => write!(f, "Invalid UTF-8 sequence at offset {} in record {}.", self.offset, record),
+            None => write!(f, "Invalid UTF-8 sequence at offset {}.", self.offset),
+        }
+    }
+}
+
+impl std::error::Error for Utf8Error {}
+
+///Returns the length of the ASCII prefix of ``data``. Checks 8 bytes at a time.
+fn ascii_prefix_len(data: &[u8]) -> usize {
+    const HIGH_BITS: u64 = 0x8080808080808080;
+
+    let mut pos = 0;
+
+    while pos + 8 <= data.len() {
+        let mut word = [0u8; 8];
+
+        word.copy_from_slice(&data[pos..pos + 8]);
+
+        if u64::from_ne_bytes(word) & HIGH_BITS != 0 {
+            break;
+        }
+
+        pos += 8;
+    }
+
+    while pos < data.len() && data[pos].is_ascii() {
+        pos += 1;
+    }
+
+    pos
+}
+
+///Validates that ``data`` is UTF-8 and returns it as ``&str``. Pure ASCII data, the common case for CSV, takes a fast path.
+pub fn validate(data: &[u8]) -> Result<&str, Utf8Error> {
+    let ascii_len = ascii_prefix_len(data);
+
+    match std::str::from_utf8(&data[ascii_len..]) {
+        //The prefix is ASCII and the rest is valid, hence the whole is valid
+        Ok(_) => Ok(unsafe { std::str::from_utf8_unchecked(data) }),
+        Err(e) => Err(Utf8Error { offset: ascii_len + e.valid_up_to(), record: None }),
+    }
+}
+
+///Returns true if ``b`` is a continuation byte of a multi-byte sequence. A character never starts with one.
+fn is_continuation(b: u8) -> bool {
+    b & 0xc0 == 0x80
+}
+
+impl Parser {
+    /// Parses CSV ``data`` like ``parse()`` but the closure receives the fields as ``&[&str]``.
+    ///
+    /// Each record is validated as UTF-8 once before the closure is called for it. On the first invalid sequence,
+    /// parsing stops and an error with the offset of the sequence and the index of its record is returned. The
+    /// records before it have already been reported.
+    ///
+    /// # Example
+    /// ```
+    /// let data = "name,city\r\nZoë,Zürich\r\n";
+    /// let mut parser = rcsv::Parser::new();
+    ///
+    /// parser.parse_str::<2>(data.as_bytes(), |index, fields| {
+    ///     if index == 1 {
+    ///         assert!(fields[1] == "Zürich");
+    ///     }
+    /// }).unwrap();
+    ///
+    /// let mut parser = rcsv::Parser::new();
+    /// let result = parser.parse_str::<2>(b"aa,bb\r\ncc,\xff\r\n", |_, _| {});
+    ///
+    /// assert!(result == Err(rcsv::utf8::Utf8Error { offset: 10, record: Some(1) }));
+    /// ```
+    pub fn parse_str<const N: usize>(&mut self, data: &[u8], consumer: impl FnMut(usize, &[&str])) -> Result<(), Utf8Error> {
+        self.parse_fields_str::<N>(data, true, consumer)
+    }
+
+    /// Parses CSV ``data`` that is already known to be valid UTF-8, such as the result of ``validate()`` on a
+    /// memory mapped file. The closure receives the fields as ``&[&str]``. The records are not validated again.
+    ///
+    /// Only a record that starts in the middle of a character fails. This happens when a CR that is not followed
+    /// by a LF ends the record before it, since the parser skips the byte after the CR. Parsing then stops with an
+    /// error at the start of the record, as ``parse_str()`` would report it.
+    ///
+    /// # Example
+    /// ```
+    /// let mut parser = rcsv::Parser::new();
+    /// let result = parser.parse_utf8::<2>("a\ré,b\n", |_, _| {});
+    ///
+    /// assert!(result == Err(rcsv::utf8::Utf8Error { offset: 3, record: Some(1) }));
+    /// ```
+    pub fn parse_utf8<const N: usize>(&mut self, data: &str, consumer: impl FnMut(usize, &[&str])) -> Result<(), Utf8Error> {
+        self.parse_fields_str::<N>(data.as_bytes(), false, consumer)
+    }
+
+    ///Parses ``data`` handing out the fields as ``&str``. If ``validate_records`` is false, ``data`` must be valid UTF-8.
+    fn parse_fields_str<const N: usize>(&mut self, data: &[u8], validate_records: bool, mut consumer: impl FnMut(usize, &[&str])) -> Result<(), Utf8Error> {
+        let mut fields: [&[u8]; N] = [&[]; N];
+        let mut str_fields: [&str; N] = [""; N];
+        let mut index: usize = 0;
+
+        if self.position == 0 {
+            self.position = crate::encoding::utf8_bom_len(data);
+        }
+
+        loop {
+            let record_start = self.position;
+            let field_count = match self.parse_record(data, &mut fields) {
+                Some(count) => count,
+                None => return Ok(()),
+            };
+
+            if validate_records {
+                let mut record_end = self.position;
+
+                //The byte after a CR line end is skipped by the parser and is not a part of any field
+                if record_end >= record_start + 2 && data[record_end - 2] == b'\r' {
+                    record_end -= 1;
+                }
+
+                if let Err(e) = validate(&data[record_start..record_end]) {
+                    return Err(Utf8Error { offset: record_start + e.offset, record: Some(index) });
+                }
+            } else if data.get(record_start).is_some_and(|&b| is_continuation(b)) {
+                //A lone CR ending the previous record took the first byte of a character
+                return Err(Utf8Error { offset: record_start, record: Some(index) });
+            }
+
+            for (str_field, field) in str_fields.iter_mut().zip(&fields[0..field_count]) {
+                // SAFETY: The field is a part of valid UTF-8 data. It starts and ends next to a delimiter, quote, escape or
+                // line end, which are all ASCII since ``Parser::with_dialect()`` only accepts a dialect that passes
+                // ``Dialect::validate()``, or at the start of the record. The record was either validated above or,
+                // if ``data`` is valid UTF-8, checked to start at a character boundary.
+                *str_field = unsafe { std::str::from_utf8_unchecked(field) };
+            }
+
+            consumer(index, &str_fields[0..field_count]);
+
+            index += 1;
+        }
+    }
+}
@@ -0,0 +1,451 @@
+//!Reading CSV files stored in a ZIP archive. This is available when the ``zip`` feature is enabled.
+//!
+//!The archive is read from memory, usually the bytes of a memory mapped file. The central directory is
+//!used to list the members. Stored (uncompressed) members are parsed in place without any copying.
+//!Deflated members are decompressed on the fly into a buffer supplied by the caller, a piece at a time.
+//!
+//! # Example
+//! ```
+//! let mapper = rcsv::mmap::FileMapper::new(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/dataset.zip")).unwrap();
+//! let archive = rcsv::zip::ZipArchive::new(mapper.get_bytes()).unwrap();
+//! let mut buffer = [0u8; 64 * 1024];
+//!
+//! for entry in archive.entries() {
+//!     if !entry.name().ends_with(b".csv") {
+//!         continue;
+//!     }
+//!
+//!     let mut parser = rcsv::Parser::new();
+//!
+//!     archive.parse_member::<4>(&entry, &mut parser, &mut buffer, |index, fields| {
+//!         //Process the record
+//!     }).unwrap();
+//! }
+//! ```
+
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+use crate::deflate::{Crc32, InflateError, Inflater};
+use crate::{Error, Parser};
+
+const EOCD_SIGNATURE: u32 = 0x06054b50;
+const EOCD_SIZE: usize = 22;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+const ZIP64_LOCATOR_SIZE: usize = 20;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x06064b50;
+const CENTRAL_SIGNATURE: u32 = 0x02014b50;
+const CENTRAL_SIZE: usize = 46;
+const LOCAL_SIGNATURE: u32 = 0x04034b50;
+const LOCAL_SIZE: usize = 30;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+const FLAG_ENCRYPTED: u16 = 0x0001;
+
+///The compression method of stored (uncompressed) members.
+pub const METHOD_STORED: u16 = 0;
+///The compression method of deflated members.
+pub const METHOD_DEFLATED: u16 = 8;
+
+///The reason a ZIP archive or one of its members could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipError {
+    ///The end of central directory record was not found.
+    NotZip,
+    ///The archive is split across multiple disks.
+    MultiDisk,
+    ///The central directory is truncated or corrupt.
+    InvalidCentralDirectory,
+    ///The local header of a member is truncated or corrupt.
+    InvalidLocalHeader,
+    ///The member is encrypted.
+    Encrypted,
+    ///The member is compressed with a method other than stored or deflated.
+    UnsupportedMethod(u16),
+    ///The compressed data of the member is corrupt.
+    Deflate(InflateError),
+    ///The CRC-32 of the decompressed data does not match the one in the central directory.
+    CrcMismatch { expected: u32, actual: u32 },
+    ///The size of the decompressed data does not match the one in the central directory.
+    LengthMismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for ZipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZipError::NotZip => write!(f, "Not a ZIP archive."),
+            ZipError::MultiDisk => write!(f, "Multi-disk archives are not supported."),
+            ZipError::InvalidCentralDirectory => write!(f, "Invalid central directory."),
+            ZipError::InvalidLocalHeader => write!(f, "Invalid local file header."),
+            ZipError::Encrypted => write!(f, "Encrypted members are not supported."),
+            ZipError::UnsupportedMethod(m) => write!(f, "Unsupported compression method {}.", m),
+            ZipError::Deflate(e) => write!(f, "{}", e),
+            ZipError::CrcMismatch { expected, actual } => write!(f, "CRC mismatch. Expected {:08x} but got {:08x}.", expected, actual),
+            ZipError::LengthMismatch { expected, actual } => write!(f, "Length mismatch. Expected {} but got {}.", expected, actual),
+        }
+    }
+}
+
+impl std::error::Error for ZipError {}
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+fn read_u64(data: &[u8], pos: usize) -> u64 {
+    let mut b = [0u8; 8];
+
+    b.copy_from_slice(&data[pos..pos + 8]);
+
+    u64::from_le_bytes(b)
+}
+
+///A member of a ZIP archive as described by the central directory.
+#[derive(Debug, Clone, Copy)]
+pub struct ZipEntry<'a> {
+    name: &'a [u8],
+    flags: u16,
+    method: u16,
+    crc32: u32,
+    compressed_size: u64,
+    size: u64,
+    header_offset: u64,
+}
+
+impl<'a> ZipEntry<'a> {
+    ///Returns the path of the member in the archive. Directories end with a ``/``.
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    ///Returns the compression method. See ``METHOD_STORED`` and ``METHOD_DEFLATED``.
+    pub fn method(&self) -> u16 {
+        self.method
+    }
+
+    ///Returns the CRC-32 of the uncompressed data.
+    pub fn crc32(&self) -> u32 {
+        self.crc32
+    }
+
+    ///Returns the size of the compressed data.
+    pub fn compressed_size(&self) -> u64 {
+        self.compressed_size
+    }
+
+    ///Returns the size of the uncompressed data.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    ///Returns true if the member is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with(b"/")
+    }
+}
+
+///Reads an entry of the central directory at ``pos``. Returns the entry and the position of the next one.
+fn read_central_entry(data: &[u8], pos: usize) -> Result<(ZipEntry<'_>, usize), ZipError> {
+    if pos + CENTRAL_SIZE > data.len() || read_u32(data, pos) != CENTRAL_SIGNATURE {
+        return Err(ZipError::InvalidCentralDirectory);
+    }
+
+    let name_len = read_u16(data, pos + 28) as usize;
+    let extra_len = read_u16(data, pos + 30) as usize;
+    let comment_len = read_u16(data, pos + 32) as usize;
+    let name_start = pos + CENTRAL_SIZE;
+    let extra_start = name_start + name_len;
+    let next = extra_start + extra_len + comment_len;
+
+    if next > data.len() {
+        return Err(ZipError::InvalidCentralDirectory);
+    }
+
+    let mut entry = ZipEntry {
+        name: &data[name_start..extra_start],
+        flags: read_u16(data, pos + 8),
+        method: read_u16(data, pos + 10),
+        crc32: read_u32(data, pos + 16),
+        compressed_size: read_u32(data, pos + 20) as u64,
+        size: read_u32(data, pos + 24) as u64,
+        header_offset: read_u32(data, pos + 42) as u64,
+    };
+
+    //Values that do not fit in 32 bits are in the ZIP64 extra field, in this order.
+    let mut extra = &data[extra_start..extra_start + extra_len];
+
+    while extra.len() >= 4 {
+        let id = read_u16(extra, 0);
+        let len = read_u16(extra, 2) as usize;
+
+        if 4 + len > extra.len() {
+            return Err(ZipError::InvalidCentralDirectory);
+        }
+
+        if id == ZIP64_EXTRA_ID {
+            let mut field = &extra[4..4 + len];
+
+            for value in [&mut entry.size, &mut entry.compressed_size, &mut entry.header_offset] {
+                if *value == 0xffffffff {
+                    if field.len() < 8 {
+                        return Err(ZipError::InvalidCentralDirectory);
+                    }
+
+                    *value = read_u64(field, 0);
+                    field = &field[8..];
+                }
+            }
+        }
+
+        extra = &extra[4 + len..];
+    }
+
+    Ok((entry, next))
+}
+
+///A ZIP archive held in memory.
+pub struct ZipArchive<'a> {
+    data: &'a [u8],
+    directory_offset: usize,
+    entry_count: u64,
+}
+
+impl<'a> ZipArchive<'a> {
+    ///Opens the archive in ``data``. The central directory is validated but nothing is allocated.
+    pub fn new(data: &'a [u8]) -> Result<ZipArchive<'a>, ZipError> {
+        if data.len() < EOCD_SIZE {
+            return Err(ZipError::NotZip);
+        }
+
+        //The end of central directory record is followed by a comment of up to 64 KB.
+        let lowest = data.len().saturating_sub(EOCD_SIZE + 0xffff);
+        let mut eocd = data.len() - EOCD_SIZE;
+
+        loop {
+            if read_u32(data, eocd) == EOCD_SIGNATURE && eocd + EOCD_SIZE + read_u16(data, eocd + 20) as usize == data.len() {
+                break;
+            }
+
+            if eocd == lowest {
+                return Err(ZipError::NotZip);
+            }
+
+            eocd -= 1;
+        }
+
+        if read_u16(data, eocd + 4) != 0 || read_u16(data, eocd + 6) != 0 {
+            return Err(ZipError::MultiDisk);
+        }
+
+        let mut entry_count = read_u16(data, eocd + 10) as u64;
+        let mut directory_size = read_u32(data, eocd + 12) as u64;
+        let mut directory_offset = read_u32(data, eocd + 16) as u64;
+
+        if eocd >= ZIP64_LOCATOR_SIZE && read_u32(data, eocd - ZIP64_LOCATOR_SIZE) == ZIP64_LOCATOR_SIGNATURE {
+            let record = read_u64(data, eocd - ZIP64_LOCATOR_SIZE + 8) as usize;
+
+            if record.checked_add(56).is_none_or(|end| end > data.len()) || read_u32(data, record) != ZIP64_EOCD_SIGNATURE {
+                return Err(ZipError::InvalidCentralDirectory);
+            }
+
+            entry_count = read_u64(data, record + 32);
+            directory_size = read_u64(data, record + 40);
+            directory_offset = read_u64(data, record + 48);
+        }
+
+        if directory_offset.checked_add(directory_size).is_none_or(|end| end > data.len() as u64) {
+            return Err(ZipError::InvalidCentralDirectory);
+        }
+
+        let archive = ZipArchive {
+            data,
+            directory_offset: directory_offset as usize,
+            entry_count,
+        };
+
+        let mut pos = archive.directory_offset;
+
+        for _ in 0..entry_count {
+            let (_, next) = read_central_entry(data, pos)?;
+
+            pos = next;
+        }
+
+        Ok(archive)
+    }
+
+    ///Returns the number of members in the archive, including directories.
+    pub fn len(&self) -> usize {
+        self.entry_count as usize
+    }
+
+    ///Returns true if the archive has no members.
+    pub fn is_empty(&self) -> bool {
+        self.entry_count == 0
+    }
+
+    ///Returns an iterator over the members in the order of the central directory.
+    pub fn entries(&self) -> ZipEntries<'a> {
+        ZipEntries {
+            data: self.data,
+            pos: self.directory_offset,
+            remaining: self.entry_count,
+        }
+    }
+
+    ///Returns the member with the path ``name``.
+    pub fn find(&self, name: &str) -> Option<ZipEntry<'a>> {
+        self.entries().find(|entry| entry.name == name.as_bytes())
+    }
+
+    ///Returns the compressed data of ``entry``. For a stored member this is the content of the member.
+    pub fn raw_data(&self, entry: &ZipEntry<'a>) -> Result<&'a [u8], ZipError> {
+        let data = self.data;
+        let pos = entry.header_offset as usize;
+
+        if pos.checked_add(LOCAL_SIZE).is_none_or(|end| end > data.len()) || read_u32(data, pos) != LOCAL_SIGNATURE {
+            return Err(ZipError::InvalidLocalHeader);
+        }
+
+        let start = pos + LOCAL_SIZE + read_u16(data, pos + 26) as usize + read_u16(data, pos + 28) as usize;
+
+        match (start as u64).checked_add(entry.compressed_size) {
+            Some(end) if end <= data.len() as u64 => Ok(&data[start..end as usize]),
+            _ => Err(ZipError::InvalidLocalHeader),
+        }
+    }
+
+    ///Returns a reader of the uncompressed content of ``entry``. The CRC-32 and size are verified at the end of the data.
+    pub fn reader(&self, entry: &ZipEntry<'a>) -> Result<ZipReader<'a>, ZipError> {
+        if entry.flags & FLAG_ENCRYPTED != 0 {
+            return Err(ZipError::Encrypted);
+        }
+
+        let raw = self.raw_data(entry)?;
+        let source = match entry.method {
+            METHOD_STORED => Source::Stored(raw),
+            METHOD_DEFLATED => Source::Deflated(Box::new(Inflater::new(raw))),
+            m => return Err(ZipError::UnsupportedMethod(m)),
+        };
+
+        Ok(ZipReader {
+            source,
+            crc: Crc32::new(),
+            size: 0,
+            expected_crc: entry.crc32,
+            expected_size: entry.size,
+        })
+    }
+
+    ///Parses the CSV member ``entry`` using ``parser``. The closure is called for every record exactly as in ``Parser::parse()``.
+    ///
+    ///A stored member is parsed in place and ``buffer`` is not used. Its CRC-32 and size are verified before the
+    ///first record is reported. A deflated member is decompressed into ``buffer`` a piece at a time as in
+    ///``Parser::parse_reader()``, hence the largest record must fit in it. It is verified at the end of the data.
+    pub fn parse_member<const N: usize>(&self, entry: &ZipEntry<'a>, parser: &mut Parser, buffer: &mut [u8], consumer: impl FnMut(usize, &[&[u8]])) -> Result<(), Error> {
+        if entry.flags & FLAG_ENCRYPTED == 0 && entry.method == METHOD_STORED {
+            let data = self.raw_data(entry)?;
+
+            verify(entry, data)?;
+            parser.parse::<N>(data, consumer);
+
+            return Ok(());
+        }
+
+        parser.parse_stream::<N, _>(self.reader(entry)?, buffer, consumer)
+    }
+}
+
+///Checks the size and CRC-32 of the uncompressed ``data`` of ``entry`` against the central directory.
+fn verify(entry: &ZipEntry<'_>, data: &[u8]) -> Result<(), ZipError> {
+    if data.len() as u64 != entry.size {
+        return Err(ZipError::LengthMismatch { expected: entry.size, actual: data.len() as u64 });
+    }
+
+    let mut crc = Crc32::new();
+
+    crc.update(data);
+
+    if crc.value() != entry.crc32 {
+        return Err(ZipError::CrcMismatch { expected: entry.crc32, actual: crc.value() });
+    }
+
+    Ok(())
+}
+
+///Iterates over the members of a ``ZipArchive``.
+pub struct ZipEntries<'a> {
+    data: &'a [u8],
+    pos: usize,
+    remaining: u64,
+}
+
+impl<'a> Iterator for ZipEntries<'a> {
+    type Item = ZipEntry<'a>;
+
+    fn next(&mut self) -> Option<ZipEntry<'a>> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        //The directory was validated when the archive was opened
+        let (entry, next) = read_central_entry(self.data, self.pos).ok()?;
+
+        self.pos = next;
+        self.remaining -= 1;
+
+        Some(entry)
+    }
+}
+
+enum Source<'a> {
+    Stored(&'a [u8]),
+    Deflated(Box<Inflater<&'a [u8]>>),
+}
+
+///Reads the uncompressed content of a ZIP member. Corrupt data is reported as an ``io::Error`` of kind
+///``InvalidData`` that wraps a ``ZipError``. The parsers convert it to ``Error::Zip``.
+pub struct ZipReader<'a> {
+    source: Source<'a>,
+    crc: Crc32,
+    size: u64,
+    expected_crc: u32,
+    expected_size: u64,
+}
+
+impl Read for ZipReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = match &mut self.source {
+            Source::Stored(data) => data.read(buf),
+            Source::Deflated(inflater) => inflater.read(buf),
+        };
+
+        let n = match result {
+            Ok(n) => n,
+            Err(e) => {
+                return Err(match crate::error::take_inner::<InflateError>(e) {
+                    Ok(inner) => io::Error::new(ErrorKind::InvalidData, ZipError::Deflate(inner)),
+                    Err(e) => e,
+                })
+            }
+        };
+
+        self.crc.update(&buf[0..n]);
+        self.size += n as u64;
+
+        if n == 0 && !buf.is_empty() {
+            if self.size != self.expected_size {
+                return Err(io::Error::new(ErrorKind::InvalidData, ZipError::LengthMismatch { expected: self.expected_size, actual: self.size }));
+            }
+
+            if self.crc.value() != self.expected_crc {
+                return Err(io::Error::new(ErrorKind::InvalidData, ZipError::CrcMismatch { expected: self.expected_crc, actual: self.crc.value() }));
+            }
+        }
+
+        Ok(n)
+    }
+}
@@ -201,4 +201,1634 @@ fn test_parse_number() {
     });
 
     assert!(f64::abs(total - (-0.114442428)) < 0.0001);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_parse_reader() {
+    let str =
+"aa,bb,cc\r\n\
+dd,\"e\r\ne\",ff\r\n\
+gg,hh,ii\r\n\
+jj,kk";
+    //The buffer is smaller than the data to force records to be carried over between reads
+    let mut buffer = [0u8; 16];
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    parser.parse_reader::<3, _>(str.as_bytes(), &mut buffer, |index, fields| {
+        assert!(index < 3);
+
+        if index == 0 {
+            assert!(fields[0] == "aa".as_bytes());
+        } else if index == 1 {
+            assert!(fields[1] == "e\r\ne".as_bytes());
+        } else {
+            assert!(fields[2] == "ii".as_bytes());
+        }
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 3);
+
+    let mut small = [0u8; 4];
+    let mut parser = rcsv::Parser::new();
+
+    match parser.parse_reader::<3, _>(str.as_bytes(), &mut small, |_, _| {}) {
+        Err(rcsv::Error::RecordTooLong { offset }) => assert!(offset == 0),
+        _ => panic!("Expected RecordTooLong"),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn check_gzip_records(index: usize, fields: &[&[u8]]) {
+    assert!(fields.len() == 4);
+    assert!(fields[0] == index.to_string().as_bytes());
+    assert!(fields[1] == format!("Name \"\"{}\"\"", index).as_bytes());
+    assert!(fields[2] == format!("multi\nline {}", index % 97).as_bytes());
+    assert!(fields[3] == (index * 3).to_string().as_bytes());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_reader() {
+    let path = env!("CARGO_MANIFEST_DIR");
+
+    for (file, expected) in [("records.csv.gz", 4000), ("records_stored.csv.gz", 500)] {
+        let file = std::fs::File::open(format!("{path}/resources/{file}")).unwrap();
+        let mut buffer = [0u8; 256];
+        let mut parser = rcsv::Parser::new();
+        let mut count = 0;
+
+        parser.parse_reader::<4, _>(file, &mut buffer, |index, fields| {
+            check_gzip_records(index, fields);
+
+            count += 1;
+        }).unwrap();
+
+        assert!(count == expected);
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_mapped() {
+    let path = env!("CARGO_MANIFEST_DIR");
+    let mapper = rcsv::mmap::FileMapper::new(format!("{path}/resources/test1.csv.gz")).unwrap();
+    let mut buffer = [0u8; 1024];
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    parser.parse_auto::<3>(mapper.get_bytes(), &mut buffer, |index, fields| {
+        if index == 0 {
+            assert!(fields[0] == "aa".as_bytes());
+        } else {
+            assert!(fields[0] == "dd".as_bytes());
+        }
+
+        count += 1;
+    }).unwrap();
+
+    //As with uncompressed data, the last line without a line end is not reported
+    assert!(count == 2);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_multi_member() {
+    let path = env!("CARGO_MANIFEST_DIR");
+    let mut data = std::fs::read(format!("{path}/resources/records_stored.csv.gz")).unwrap();
+
+    data.extend(std::fs::read(format!("{path}/resources/records_stored.csv.gz")).unwrap());
+
+    let mut buffer = [0u8; 1024];
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    parser.parse_auto::<4>(&data, &mut buffer, |index, fields| {
+        check_gzip_records(index % 500, fields);
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 1000);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip_corrupt() {
+    let path = env!("CARGO_MANIFEST_DIR");
+    let mut data = std::fs::read(format!("{path}/resources/records.csv.gz")).unwrap();
+    let len = data.len();
+
+    //Damage the CRC in the trailer
+    data[len - 8] ^= 0xff;
+
+    let mut buffer = [0u8; 1024];
+    let mut parser = rcsv::Parser::new();
+
+    match parser.parse_auto::<4>(&data, &mut buffer, |_, _| {}) {
+        Err(rcsv::Error::Gzip(rcsv::gzip::GzipError::CrcMismatch { .. })) => {}
+        other => panic!("Expected CrcMismatch, got {:?}", other),
+    }
+
+    //Truncate the compressed data
+    let mut parser = rcsv::Parser::new();
+
+    match parser.parse_auto::<4>(&data[0..len / 2], &mut buffer, |_, _| {}) {
+        Err(rcsv::Error::Gzip(rcsv::gzip::GzipError::Deflate(rcsv::deflate::InflateError::UnexpectedEof))) => {}
+        other => panic!("Expected UnexpectedEof, got {:?}", other),
+    }
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_members() {
+    let path = env!("CARGO_MANIFEST_DIR");
+    let mapper = rcsv::mmap::FileMapper::new(format!("{path}/resources/dataset.zip")).unwrap();
+    let archive = rcsv::zip::ZipArchive::new(mapper.get_bytes()).unwrap();
+    let names: Vec<&[u8]> = archive.entries().map(|e| e.name()).collect();
+
+    assert!(archive.len() == 3);
+    assert!(names == [b"stored.csv".as_slice(), b"data/records.csv", b"README.txt"]);
+
+    //Stored member is parsed in place
+    let entry = archive.find("stored.csv").unwrap();
+    let mut buffer = [0u8; 256];
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    assert!(entry.method() == rcsv::zip::METHOD_STORED);
+
+    archive.parse_member::<3>(&entry, &mut parser, &mut [], |index, fields| {
+        if index == 0 {
+            assert!(fields[0] == "aa".as_bytes());
+        } else {
+            assert!(fields[2] == "ff".as_bytes());
+        }
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 2);
+
+    //Deflated member is decompressed into the buffer
+    let entry = archive.find("data/records.csv").unwrap();
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    assert!(entry.method() == rcsv::zip::METHOD_DEFLATED);
+    assert!(entry.size() == 83198);
+
+    archive.parse_member::<4>(&entry, &mut parser, &mut buffer, |index, fields| {
+        assert!(fields[0] == index.to_string().as_bytes());
+        assert!(fields[2] == format!("multi\nline {}", index % 97).as_bytes());
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 2000);
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_corrupt() {
+    let path = env!("CARGO_MANIFEST_DIR");
+    let mut data = std::fs::read(format!("{path}/resources/dataset.zip")).unwrap();
+
+    assert!(matches!(rcsv::zip::ZipArchive::new(&data[0..100]), Err(rcsv::zip::ZipError::NotZip)));
+
+    let archive = rcsv::zip::ZipArchive::new(&data).unwrap();
+    let entry = archive.find("data/records.csv").unwrap();
+    let middle = archive.raw_data(&entry).unwrap().as_ptr() as usize - data.as_ptr() as usize + entry.compressed_size() as usize / 2;
+
+    //Damage the compressed data in the middle of the member
+    data[middle] ^= 0x55;
+
+    let archive = rcsv::zip::ZipArchive::new(&data).unwrap();
+    let entry = archive.find("data/records.csv").unwrap();
+    let mut buffer = [0u8; 1024];
+    let mut parser = rcsv::Parser::new();
+
+    match archive.parse_member::<4>(&entry, &mut parser, &mut buffer, |_, _| {}) {
+        Err(rcsv::Error::Zip(_)) => {}
+        other => panic!("Expected a ZIP error, got {:?}", other),
+    }
+
+    //Damage the content of the stored member. It is verified before any record is reported.
+    let entry = archive.find("stored.csv").unwrap();
+    let start = archive.raw_data(&entry).unwrap().as_ptr() as usize - data.as_ptr() as usize;
+    let expected = entry.crc32();
+
+    data[start] ^= 0x01;
+
+    let archive = rcsv::zip::ZipArchive::new(&data).unwrap();
+    let entry = archive.find("stored.csv").unwrap();
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    match archive.parse_member::<3>(&entry, &mut parser, &mut [], |_, _| count += 1) {
+        Err(rcsv::Error::Zip(rcsv::zip::ZipError::CrcMismatch { expected: e, .. })) => assert!(e == expected),
+        other => panic!("Expected a CRC mismatch, got {:?}", other),
+    }
+
+    assert!(count == 0);
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("rcsv-{}-{}", name, std::process::id()));
+
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+
+    dir
+}
+
+#[test]
+fn test_dataset_align() {
+    let dir = temp_dir("dataset-align");
+
+    std::fs::write(dir.join("part-0001.csv"), "id,name\n1,aa\n2,bb\n").unwrap();
+    std::fs::write(dir.join("part-0002.csv"), "name,id,city\ncc,3,x\n").unwrap();
+    std::fs::write(dir.join("other.csv"), "not,part\n").unwrap();
+    //A part that is a symbolic link is included, a directory is not
+    std::fs::create_dir(dir.join("part-0004.csv")).unwrap();
+
+    #[cfg(unix)]
+    {
+        std::fs::write(dir.join("three.csv"), "id\n4\n").unwrap();
+        std::os::unix::fs::symlink(dir.join("three.csv"), dir.join("part-0003.csv")).unwrap();
+    }
+
+    #[cfg(not(unix))]
+    std::fs::write(dir.join("part-0003.csv"), "id\n4\n").unwrap();
+
+    let dataset = rcsv::dataset::Dataset::from_dir(&dir, "part-*.csv", rcsv::dataset::HeaderMode::Align)
+        .unwrap()
+        .with_source_column("source");
+    let columns: Vec<&[u8]> = dataset.columns().collect();
+
+    assert!(columns == [b"id".as_slice(), b"name", b"city", b"source"]);
+
+    let mut records = Vec::new();
+
+    dataset.parse::<10>(|index, fields| {
+        assert!(fields.len() == 4);
+
+        records.push((index, fields[0].to_vec(), fields[1].to_vec(), fields[2].to_vec()));
+
+        assert!(std::path::Path::new(std::str::from_utf8(fields[3]).unwrap()).starts_with(&dir));
+    });
+
+    assert!(records == [
+        (0, b"1".to_vec(), b"aa".to_vec(), b"".to_vec()),
+        (1, b"2".to_vec(), b"bb".to_vec(), b"".to_vec()),
+        (2, b"3".to_vec(), b"cc".to_vec(), b"x".to_vec()),
+        (3, b"4".to_vec(), b"".to_vec(), b"".to_vec()),
+    ]);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_dataset_strict() {
+    let dir = temp_dir("dataset-strict");
+    let first = dir.join("a.csv");
+    let second = dir.join("b.csv");
+
+    std::fs::write(&first, "id,name\n1,aa\n").unwrap();
+    std::fs::write(&second, "id,name\n2,bb\n").unwrap();
+
+    let dataset = rcsv::dataset::Dataset::new(&[&first, &second], rcsv::dataset::HeaderMode::Strict).unwrap();
+    let mut count = 0;
+
+    dataset.parse::<2>(|index, fields| {
+        assert!(fields[0] == (index + 1).to_string().as_bytes());
+
+        count += 1;
+    });
+
+    assert!(count == 2);
+
+    std::fs::write(&second, "id,title\n2,bb\n").unwrap();
+
+    match rcsv::dataset::Dataset::new(&[&first, &second], rcsv::dataset::HeaderMode::Strict) {
+        Err(rcsv::dataset::DatasetError::HeaderMismatch { path, column }) => {
+            assert!(path == second);
+            assert!(column == 1);
+        }
+        _ => panic!("Expected HeaderMismatch"),
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_follower() {
+    use std::io::Write;
+
+    let dir = temp_dir("follow");
+    let path = dir.join("log.csv");
+    let mut follower = rcsv::mmap::Follower::new(&path);
+    let mut records: Vec<(usize, Vec<u8>)> = Vec::new();
+
+    //The file does not exist yet
+    assert!(follower.poll::<2>(|_, _| {}).unwrap() == 0);
+
+    let mut file = std::fs::File::create(&path).unwrap();
+
+    file.write_all(b"aa,1\nbb,").unwrap();
+
+    assert!(follower.poll::<2>(|index, fields| records.push((index, fields[0].to_vec()))).unwrap() == 1);
+
+    //The half written line is reported once complete
+    file.write_all(b"2\r").unwrap();
+
+    assert!(follower.poll::<2>(|index, fields| records.push((index, fields[0].to_vec()))).unwrap() == 0);
+
+    file.write_all(b"\ncc,3\n").unwrap();
+
+    assert!(follower.poll::<2>(|index, fields| records.push((index, fields[0].to_vec()))).unwrap() == 2);
+    assert!(records == [(0, b"aa".to_vec()), (1, b"bb".to_vec()), (2, b"cc".to_vec())]);
+
+    //Rotation
+    drop(file);
+    std::fs::rename(&path, dir.join("log.csv.1")).unwrap();
+    std::fs::write(&path, "dd,4\n").unwrap();
+
+    records.clear();
+
+    assert!(follower.poll::<2>(|index, fields| records.push((index, fields[0].to_vec()))).unwrap() == 1);
+    assert!(records == [(3, b"dd".to_vec())]);
+    assert!(follower.restarts() == 1);
+
+    //Truncated in place and written again past the offset before the next poll, as by copytruncate
+    std::fs::write(&path, "ee,5\nff,6\n").unwrap();
+
+    records.clear();
+
+    assert!(follower.poll::<2>(|index, fields| records.push((index, fields[0].to_vec()))).unwrap() == 2);
+    assert!(records == [(4, b"ee".to_vec()), (5, b"ff".to_vec())]);
+    assert!(follower.restarts() == 2);
+
+    //Truncation
+    std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(0).unwrap();
+
+    assert!(follower.poll::<2>(|_, _| {}).unwrap() == 0);
+    assert!(follower.offset() == 0);
+    assert!(follower.restarts() == 3);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_utf8_bom() {
+    let str = "\u{feff}id,name\r\naa,bb\r\n";
+    let mut parser = rcsv::Parser::new();
+
+    parser.parse::<2>(str.as_bytes(), |index, fields| {
+        if index == 0 {
+            assert!(fields[0] == "id".as_bytes());
+        }
+    });
+
+    let mut buffer = [0u8; 64];
+    let mut parser = rcsv::Parser::new();
+
+    parser.parse_reader::<2, _>(str.as_bytes(), &mut buffer, |index, fields| {
+        if index == 0 {
+            assert!(fields[0] == "id".as_bytes());
+        }
+    }).unwrap();
+}
+
+#[test]
+fn test_utf16() {
+    let str = "id,name\r\n1,\"Zoë 😀\"\r\n2,Ünïcödé\r\n";
+    let mut buffer = [0u8; 32];
+
+    for bom in [[0xffu8, 0xfe], [0xfe, 0xff]] {
+        let mut data = bom.to_vec();
+
+        for unit in str.encode_utf16() {
+            data.extend(if bom[0] == 0xff { unit.to_le_bytes() } else { unit.to_be_bytes() });
+        }
+
+        let mut parser = rcsv::Parser::new();
+        let mut count = 0;
+
+        parser.parse_reader::<2, _>(data.as_slice(), &mut buffer, |index, fields| {
+            if index == 0 {
+                assert!(fields[0] == "id".as_bytes());
+            } else if index == 1 {
+                assert!(fields[1] == "Zoë 😀".as_bytes());
+            } else {
+                assert!(fields[1] == "Ünïcödé".as_bytes());
+            }
+
+            count += 1;
+        }).unwrap();
+
+        assert!(count == 3);
+
+        //In-memory data
+        let mut parser = rcsv::Parser::new();
+        let mut count = 0;
+
+        parser.parse_auto::<2>(&data, &mut buffer, |_, _| count += 1).unwrap();
+
+        assert!(count == 3);
+    }
+
+    //A lone high surrogate at offset 2 + 2 * 3
+    let mut data = vec![0xffu8, 0xfe];
+
+    for unit in [b'a' as u16, b',' as u16, b'b' as u16, 0xd800, b'\n' as u16] {
+        data.extend(unit.to_le_bytes());
+    }
+
+    let mut parser = rcsv::Parser::new();
+
+    match parser.parse_auto::<2>(&data, &mut buffer, |_, _| {}) {
+        Err(rcsv::Error::Utf16(rcsv::encoding::Utf16Error::UnpairedSurrogate { offset })) => assert!(offset == 8),
+        other => panic!("Expected UnpairedSurrogate, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_single_byte_encoding() {
+    use rcsv::encoding::Encoding;
+
+    let data = b"name,amount\r\nFran\xe7ois,\x80 5\r\nCaf\xe9 \xa4,\x9d\r\n";
+    let mut out = [0u8; 16];
+    let mut parser = rcsv::Parser::new();
+    let mut records: Vec<(String, String, String)> = Vec::new();
+
+    parser.parse::<2>(data, |_, fields| {
+        records.push((
+            Encoding::Windows1252.decode(fields[0], &mut out).unwrap().to_string(),
+            Encoding::Latin1.decode(fields[1], &mut out).unwrap().to_string(),
+            Encoding::Latin9.decode(fields[0], &mut out).unwrap().to_string(),
+        ));
+    });
+
+    assert!(records[0] == ("name".to_string(), "amount".to_string(), "name".to_string()));
+    assert!(records[1] == ("François".to_string(), "\u{80} 5".to_string(), "François".to_string()));
+    assert!(records[2] == ("Café ¤".to_string(), "\u{9d}".to_string(), "Café €".to_string()));
+
+    //€ is 3 bytes in UTF-8
+    assert!(Encoding::Windows1252.decoded_len(b"\x80\x80") == 6);
+    assert!(Encoding::Windows1252.decode(b"\x80\x80", &mut out[0..5]) == Err(rcsv::encoding::BufferTooSmall { required: 6 }));
+    assert!(Encoding::from_label("CP1252") == Some(Encoding::Windows1252));
+    assert!(Encoding::from_label("ISO-8859-15") == Some(Encoding::Latin9));
+}
+
+#[test]
+fn test_parse_str() {
+    let str = "name,city\r\n\"Zoë\",\"Zürich, CH\"\r\naa,😀\r\n";
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    parser.parse_str::<2>(str.as_bytes(), |index, fields| {
+        if index == 1 {
+            assert!(fields[0] == "Zoë");
+            assert!(fields[1] == "Zürich, CH");
+        } else if index == 2 {
+            assert!(fields[1] == "😀");
+        }
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 3);
+
+    //Whole data validation
+    let data = rcsv::utf8::validate(str.as_bytes()).unwrap();
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    parser.parse_utf8::<2>(data, |_, fields| {
+        assert!(fields.len() == 2);
+
+        count += 1;
+    }).unwrap();
+
+    //A lone CR makes the parser skip the first byte of "é". The next record would start in the middle of it.
+    let data = "a\ré,b\n";
+    let mut parser = rcsv::Parser::new();
+    let mut records: Vec<Vec<String>> = Vec::new();
+
+    let result = parser.parse_utf8::<4>(data, |_, fields| records.push(fields.iter().map(|f| f.to_string()).collect()));
+
+    assert!(result == Err(rcsv::utf8::Utf8Error { offset: 3, record: Some(1) }));
+    assert!(records == [vec!["a".to_string()]]);
+
+    let mut parser = rcsv::Parser::new();
+
+    assert!(parser.parse_str::<4>(data.as_bytes(), |_, _| {}) == Err(rcsv::utf8::Utf8Error { offset: 3, record: Some(1) }));
+
+    //A lone CR followed by an ASCII character loses that character but splits no character
+    let mut parser = rcsv::Parser::new();
+    let mut fields: Vec<String> = Vec::new();
+
+    parser.parse_utf8::<4>("a\rxé,b\n", |_, f| fields.extend(f.iter().map(|f| f.to_string()))).unwrap();
+
+    assert!(fields == ["a", "é", "b"]);
+
+    assert!(count == 3);
+
+    //Invalid sequence in the third record. A truncated 3 byte sequence.
+    let data = b"aa,bb\r\ncc,dd\r\nee,f\xe2\x82\r\n";
+    let mut parser = rcsv::Parser::new();
+    let mut count = 0;
+
+    let result = parser.parse_str::<2>(data, |_, _| count += 1);
+
+    assert!(result == Err(rcsv::utf8::Utf8Error { offset: 18, record: Some(2) }));
+    assert!(count == 2);
+    assert!(rcsv::utf8::validate(data) == Err(rcsv::utf8::Utf8Error { offset: 18, record: None }));
+
+    //A non-ASCII delimiter would split "é" in the middle. Such a dialect is rejected.
+    let dialect = rcsv::Dialect { delimiter: 0xc3, ..Default::default() };
+
+    assert!(rcsv::Parser::with_dialect(dialect).is_err());
+
+    let dialect = rcsv::Dialect { delimiter: b';', quote: b'\'', escape: Some(b'\\') };
+    let mut parser = rcsv::Parser::with_dialect(dialect).unwrap();
+    let mut count = 0;
+
+    parser.parse_str::<2>("Zoë;'Zü\\'rich; CH'\n".as_bytes(), |_, fields| {
+        assert!(fields[0] == "Zoë");
+        assert!(fields[1] == "Zü\\'rich; CH");
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 1);
+}
+
+#[test]
+fn test_parse_number_invalid_utf8() {
+    let mut n: f64 = 0.0;
+
+    assert!(!rcsv::parse_number(b"1.5\xff", &mut n));
+    assert!(rcsv::parse_number(b" 1.5 ", &mut n));
+    assert!(n == 1.5);
+}
+
+#[test]
+fn test_dialect() {
+    let str = "aa|'b|b'|\"c\\|c\"\n";
+    let dialect = rcsv::Dialect { delimiter: b'|', quote: b'\'', escape: Some(b'\\') };
+    let mut parser = rcsv::Parser::with_dialect(dialect).unwrap();
+    let mut count = 0;
+
+    parser.parse::<3>(str.as_bytes(), |_, fields| {
+        assert!(fields[0] == "aa".as_bytes());
+        assert!(fields[1] == "b|b".as_bytes());
+        assert!(fields[2] == "\"c\\|c\"".as_bytes());
+
+        count += 1;
+    });
+
+    assert!(count == 1);
+}
+
+#[test]
+fn test_invalid_dialect() {
+    use rcsv::{Dialect, DialectError};
+
+    let rejected = [
+        (Dialect { delimiter: 0xc3, ..Default::default() }, DialectError::NotAscii(0xc3)),
+        (Dialect { quote: 0xa9, ..Default::default() }, DialectError::NotAscii(0xa9)),
+        (Dialect { escape: Some(0x80), ..Default::default() }, DialectError::NotAscii(0x80)),
+        (Dialect { delimiter: b'\r', ..Default::default() }, DialectError::LineEnd(b'\r')),
+        (Dialect { quote: b'\n', ..Default::default() }, DialectError::LineEnd(b'\n')),
+        (Dialect { escape: Some(b'\n'), ..Default::default() }, DialectError::LineEnd(b'\n')),
+        (Dialect { escape: Some(b'\r'), ..Default::default() }, DialectError::LineEnd(b'\r')),
+        (Dialect { delimiter: b'"', ..Default::default() }, DialectError::Clash(b'"')),
+        (Dialect { escape: Some(b','), ..Default::default() }, DialectError::Clash(b',')),
+        (Dialect { escape: Some(b'"'), ..Default::default() }, DialectError::Clash(b'"')),
+    ];
+
+    for (dialect, error) in rejected {
+        assert!(dialect.validate() == Err(error));
+        assert!(rcsv::Parser::with_dialect(dialect).err() == Some(error));
+    }
+
+    assert!(rcsv::Parser::with_dialect(Dialect { delimiter: b'\t', quote: b'\'', escape: Some(b'\\') }).is_ok());
+}
+
+#[test]
+fn test_sniff() {
+    use rcsv::encoding::{Endian, Utf16Decoder};
+    use rcsv::sniff::{sniff, Terminator, TextEncoding};
+
+    let tsv = "name\tage\tcity\nAlice\t30\tParis\nBob\t25\tLondon\nCarol\t41\tRome\n";
+    let result = sniff(tsv.as_bytes());
+
+    assert!(result.dialect.delimiter == b'\t');
+    assert!(result.terminator == Terminator::Lf);
+    assert!(result.encoding == TextEncoding::Ascii);
+    assert!(result.has_header);
+    assert!(result.columns == 3);
+    assert!(result.confidence < 1.0);
+
+    let data = "1,2.5,3\r\n4,5.5,6\r\n7,8.5,9\r\n10,11.5,12\r\n13,14.5,15\r\n16,17";
+    let result = sniff(data.as_bytes());
+
+    assert!(result.dialect.delimiter == b',');
+    assert!(result.terminator == Terminator::CrLf);
+    assert!(!result.has_header);
+    assert!(result.columns == 3);
+    assert!(result.confidence == 1.0);
+
+    let escaped = "id|text\n1|\"say \\\"hi\\\"\"\n2|\"a|b\"\n3|plain\n";
+    let result = sniff(escaped.as_bytes());
+
+    assert!(result.dialect.delimiter == b'|');
+    assert!(result.dialect.escape == Some(b'\\'));
+    assert!(result.columns == 2);
+
+    let mut count = 0;
+
+    result.parser().unwrap().parse::<2>(escaped.as_bytes(), |index, fields| {
+        if index == 2 {
+            assert!(fields[1] == "a|b".as_bytes());
+        }
+
+        count += 1;
+    });
+
+    assert!(count == 4);
+
+    let quoted = "'a, b',c\n'd, e',f\n'g, h',i\n";
+
+    assert!(sniff(quoted.as_bytes()).dialect.quote == b'\'');
+
+    let latin1 = b"name;city\nJos\xe9;M\xe1laga\nAnn;Ume\xe5\n";
+    let result = sniff(latin1);
+
+    assert!(result.dialect.delimiter == b';');
+    assert!(result.encoding == TextEncoding::SingleByte);
+
+    let utf16: Vec<u8> = tsv.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+    let result = sniff(&utf16);
+
+    assert!(result.encoding == TextEncoding::Utf16Le);
+    assert!(result.dialect.delimiter == b'\t');
+    assert!(result.columns == 3);
+
+    //Without a byte order mark the data is transcoded as the sniffed encoding says
+    let mut buffer = [0u8; 256];
+    let mut count = 0;
+
+    result.parser().unwrap().parse_reader::<3, _>(Utf16Decoder::new(utf16.as_slice(), Endian::Little), &mut buffer, |index, fields| {
+        if index == 1 {
+            assert!(fields[2] == "Paris".as_bytes());
+        }
+
+        count += 1;
+    }).unwrap();
+
+    assert!(count == 4);
+
+    let bom = "\u{feff}a;b\n1;2\n";
+
+    assert!(sniff(bom.as_bytes()).encoding == TextEncoding::Utf8Bom);
+
+    //The parser would drop the byte after each lone CR
+    let result = sniff(b"id,name\r1,alice\r2,bob\r");
+
+    assert!(result.terminator == Terminator::Cr);
+    assert!(result.parser().is_none());
+}
+
+#[test]
+fn test_map_error() {
+    use rcsv::mmap::{FileMapper, MapStep};
+    use std::io::ErrorKind;
+
+    let dir = temp_dir("map-error");
+    let missing = dir.join("missing.csv");
+
+    let e = match FileMapper::new(&missing) {
+        Ok(_) => panic!("The file should not exist."),
+        Err(e) => e,
+    };
+
+    assert!(e.kind() == ErrorKind::NotFound);
+    assert!(e.step() == MapStep::Open);
+    assert!(e.path() == missing);
+    assert!(e.to_string().contains("missing.csv"));
+
+    let io_error: std::io::Error = e.into();
+
+    assert!(io_error.kind() == ErrorKind::NotFound);
+
+    //A directory opens fine on Unix but can not be mapped
+    assert!(FileMapper::new(&dir).is_err());
+
+    match rcsv::dataset::Dataset::new(&[&missing], rcsv::dataset::HeaderMode::Strict) {
+        Err(rcsv::dataset::DatasetError::Map(e)) => assert!(e.path() == missing),
+        _ => panic!("Expected a map error."),
+    }
+}
+
+#[test]
+fn test_map_empty_and_open_files() {
+    use rcsv::mmap::FileMapper;
+
+    let dir = temp_dir("map-empty");
+    let empty = dir.join("empty.csv");
+
+    std::fs::write(&empty, "").unwrap();
+
+    let mapper = FileMapper::new(&empty).unwrap();
+    let mut count = 0;
+
+    assert!(mapper.size() == 0);
+    assert!(mapper.get_bytes().is_empty());
+
+    rcsv::Parser::new().parse::<3>(mapper.get_bytes(), |_, _| count += 1);
+
+    assert!(count == 0);
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/test1.csv");
+    let expected = std::fs::read(&path).unwrap();
+    let file = std::fs::File::open(&path).unwrap();
+    let mapper = FileMapper::from_file(file).unwrap();
+
+    assert!(mapper.get_bytes() == expected.as_slice());
+
+    #[cfg(unix)]
+    {
+        let fd = std::os::fd::OwnedFd::from(std::fs::File::open(&path).unwrap());
+        let mapper = FileMapper::from_fd(fd).unwrap();
+
+        assert!(mapper.get_bytes() == expected.as_slice());
+    }
+
+    //A write only file can not be mapped for reading
+    #[cfg(unix)]
+    {
+        let file = std::fs::OpenOptions::new().write(true).open(dir.join("empty.csv")).unwrap();
+
+        std::fs::write(dir.join("empty.csv"), "a,b\n").unwrap();
+
+        match FileMapper::from_file(file) {
+            Ok(_) => panic!("A write only file should not map."),
+            Err(e) => {
+                assert!(e.path().as_os_str().is_empty());
+                assert!(e.step() == rcsv::mmap::MapStep::Map);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_map_options() {
+    use rcsv::mmap::{Advice, FileMapper, MapOptions};
+
+    let path = format!("{}/resources/test1.csv", env!("CARGO_MANIFEST_DIR"));
+    let expected = std::fs::read(&path).unwrap();
+    let options = MapOptions::new()
+        .advice(Advice::Sequential)
+        .populate(true)
+        .huge_pages(true)
+        .lock(true);
+    let mapper = options.map(&path).unwrap();
+
+    assert!(mapper.get_bytes() == expected.as_slice());
+
+    mapper.advise(3..7, Advice::Random).unwrap();
+    mapper.advise(0..mapper.size() + 100, Advice::WillNeed).unwrap();
+    mapper.unlock(0..mapper.size()).unwrap();
+    mapper.lock(1..2).unwrap();
+
+    let mapper = FileMapper::with_options(&path, &MapOptions::new().advice(Advice::Random)).unwrap();
+
+    assert!(mapper.get_bytes() == expected.as_slice());
+
+    let dir = temp_dir("map-options");
+    let empty = dir.join("empty.csv");
+
+    std::fs::write(&empty, "").unwrap();
+
+    let mapper = options.map_file(std::fs::File::open(&empty).unwrap()).unwrap();
+
+    assert!(mapper.get_bytes().is_empty());
+
+    mapper.advise(0..10, Advice::Sequential).unwrap();
+}
+
+#[test]
+fn test_parse_windows() {
+    use rcsv::mmap::WindowedMapper;
+
+    let dir = temp_dir("windows");
+    let path = dir.join("large.csv");
+    let mut data = String::from("\u{feff}");
+
+    for i in 0..5000 {
+        data.push_str(&format!("{},\"name {}\r\n{}\",{}\r\n", i, i, "x".repeat(i % 37), i * 7));
+    }
+
+    std::fs::write(&path, &data).unwrap();
+
+    let mut expected = Vec::new();
+
+    rcsv::Parser::new().parse::<3>(data.as_bytes(), |index, fields| {
+        expected.push((index, fields.concat()));
+    });
+
+    //The smallest window is two pages
+    let mapper = WindowedMapper::new(&path, 1).unwrap();
+    let mut actual = Vec::new();
+
+    assert!(mapper.size() == data.len() as u64);
+    assert!(mapper.window_size() > 0 && mapper.window_size() < data.len());
+
+    rcsv::Parser::new().parse_windows::<3>(&mapper, |index, fields| {
+        actual.push((index, fields.concat()));
+    }).unwrap();
+
+    assert!(actual.len() == 5000);
+    assert!(actual == expected);
+
+    let window = mapper.window(mapper.size() - 1).unwrap();
+
+    assert!(window.offset() + window.get_bytes().len() as u64 == mapper.size());
+    assert!(mapper.window(mapper.size() + 10).unwrap().get_bytes().is_empty());
+
+    //A record longer than a window
+    let long = dir.join("long.csv");
+
+    std::fs::write(&long, format!("a,b\r\n{},c\r\n", "y".repeat(3 * mapper.window_size()))).unwrap();
+
+    let mapper = WindowedMapper::new(&long, 1).unwrap();
+
+    match rcsv::Parser::new().parse_windows::<3>(&mapper, |_, _| {}) {
+        Err(rcsv::Error::RecordTooLong { offset }) => assert!(offset == 5),
+        _ => panic!("Expected RecordTooLong."),
+    }
+
+    match rcsv::mmap::WindowedMapper::new(dir.join("missing.csv"), 1) {
+        Err(e) => assert!(e.kind() == std::io::ErrorKind::NotFound),
+        _ => panic!("Expected RecordTooLong."),
+    }
+
+    let empty = dir.join("empty.csv");
+
+    std::fs::write(&empty, "").unwrap();
+
+    let mapper = WindowedMapper::new(&empty, 4096).unwrap();
+
+    rcsv::Parser::new().parse_windows::<3>(&mapper, |_, _| panic!("No records expected.")).unwrap();
+}
+
+//The resident size is not available on Windows
+#[cfg(unix)]
+#[test]
+fn test_parse_releasing() {
+    use rcsv::mmap::{FileMapper, MapOptions};
+
+    let dir = temp_dir("releasing");
+    let path = dir.join("large.csv");
+    let mut data = String::new();
+
+    //Large enough to be parsed in several chunks
+    for i in 0..400_000 {
+        data.push_str(&format!("{},name {},{}\n", i, i, "z".repeat(i % 41)));
+    }
+
+    data.push_str(&format!("last,\"{}\",x\n", "w".repeat(20 * 1024 * 1024)));
+
+    std::fs::write(&path, &data).unwrap();
+
+    let mut expected = Vec::new();
+
+    rcsv::Parser::new().parse::<3>(data.as_bytes(), |index, fields| {
+        expected.push((index, fields.concat()));
+    });
+
+    let mapper = MapOptions::new().populate(true).map(&path).unwrap();
+    let resident = mapper.resident_size().unwrap();
+
+    assert!(resident >= mapper.size() && resident <= mapper.size() + 64 * 1024);
+
+    let mut actual = Vec::new();
+
+    rcsv::Parser::new().parse_releasing::<3>(&mapper, 4096, |index, fields| {
+        actual.push((index, fields.concat()));
+    }).unwrap();
+
+    assert!(actual == expected);
+    assert!(mapper.resident_size().unwrap() <= resident);
+
+    //Released data is read from the file again
+    mapper.release(0..mapper.size()).unwrap();
+
+    assert!(mapper.get_bytes() == data.as_bytes());
+
+    let empty = dir.join("empty.csv");
+
+    std::fs::write(&empty, "").unwrap();
+
+    let mapper = FileMapper::new(&empty).unwrap();
+
+    assert!(mapper.resident_size().unwrap() == 0);
+
+    rcsv::Parser::new().parse_releasing::<3>(&mapper, 0, |_, _| panic!("No records expected.")).unwrap();
+
+    //A range that does not start at a page boundary. Every page before the last few is released. The records are
+    //64 bytes long, 131072 of them are parsed between releases.
+    let ranged = dir.join("ranged.csv");
+    let mut data = format!("{}\n", "p".repeat(99));
+
+    for i in 0..6 * 131_072 + 10 {
+        data.push_str(&format!("{:08},{}\n", i, "x".repeat(54)));
+    }
+
+    let mut file = std::fs::File::create(&ranged).unwrap();
+
+    std::io::Write::write_all(&mut file, data.as_bytes()).unwrap();
+    //Written back, so that the released pages can leave the page cache
+    file.sync_all().unwrap();
+
+    let mapper = MapOptions::new().populate(true).map_range(&ranged, 100, usize::MAX).unwrap();
+    let mut count = 0;
+
+    rcsv::Parser::new().parse_releasing::<2>(&mapper, 0, |_, _| count += 1).unwrap();
+
+    assert!(count == 6 * 131_072 + 10);
+    //The page cache may keep the pages at either end in blocks of up to 2 MiB
+    assert!(mapper.resident_size().unwrap() <= 4 * 1024 * 1024);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_map_check_and_snapshot() {
+    use rcsv::mmap::{FileChange, FileMapper, MapOptions};
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    let dir = temp_dir("snapshot");
+    let path = dir.join("data.csv");
+    let data = "aa,bb\n".repeat(5000);
+
+    std::fs::write(&path, &data).unwrap();
+
+    let shared = FileMapper::new(&path).unwrap();
+    let snapshot = MapOptions::new().snapshot(true).map(&path).unwrap();
+
+    assert!(shared.check().unwrap().is_unchanged());
+    assert!(snapshot.get_bytes() == data.as_bytes());
+
+    //Same size, different content
+    std::fs::write(&path, "cc,dd\n".repeat(5000)).unwrap();
+    std::fs::File::options().write(true).open(&path).unwrap().set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1000)).unwrap();
+
+    assert!(shared.check().unwrap() == FileChange::Modified);
+    assert!(&shared.get_bytes()[0..2] == b"cc");
+
+    std::fs::File::options().append(true).open(&path).unwrap().set_len(10).unwrap();
+
+    assert!(shared.check().unwrap() == FileChange::Truncated { size: 10 });
+    assert!(snapshot.check().unwrap() == FileChange::Truncated { size: 10 });
+
+    //Reading the shared mapping past 10 bytes would raise SIGBUS. The snapshot is intact.
+    assert!(snapshot.get_bytes() == data.as_bytes());
+
+    drop(shared);
+
+    std::fs::write(&path, "aa,bb\ncc,dd\n").unwrap();
+
+    let locked = MapOptions::new().shared_lock(true).map(&path).unwrap();
+    let writer = std::fs::File::options().write(true).open(&path).unwrap();
+
+    assert!(writer.try_lock().is_err());
+    assert!(writer.try_lock_shared().is_ok());
+
+    writer.unlock().unwrap();
+    std::fs::File::options().append(true).open(&path).unwrap().write_all(b"ee,ff\n").unwrap();
+
+    assert!(locked.check().unwrap() == FileChange::Grown { size: 18 });
+
+    drop(locked);
+
+    assert!(writer.try_lock().is_ok());
+}
+
+#[test]
+fn test_mapped_range() {
+    use rcsv::mmap::{FileMapper, MappedRange};
+    use std::sync::Arc;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<FileMapper>();
+    assert_send_sync::<MappedRange>();
+
+    let dir = temp_dir("mapped-range");
+    let path = dir.join("data.csv");
+    let mut data = String::new();
+
+    for i in 0..10000 {
+        data.push_str(&format!("{},{}\n", i, i * 2));
+    }
+
+    std::fs::write(&path, &data).unwrap();
+
+    let mapper = Arc::new(FileMapper::new(&path).unwrap());
+    let bytes = mapper.get_bytes();
+    let mut parts = Vec::new();
+    let mut start = 0;
+
+    //Four parts split after a line end
+    for i in 1..4 {
+        let end = bytes[i * bytes.len() / 4..].iter().position(|&b| b == b'\n').unwrap() + i * bytes.len() / 4 + 1;
+
+        parts.push(MappedRange::new(mapper.clone(), start..end));
+        start = end;
+    }
+
+    parts.push(MappedRange::new(mapper.clone(), start..bytes.len()));
+
+    let sums: Vec<usize> = std::thread::scope(|scope| {
+        let workers: Vec<_> = parts.iter().cloned().map(|part| {
+            scope.spawn(move || {
+                let mut sum = 0;
+
+                rcsv::Parser::new().parse::<2>(part.get_bytes(), |_, fields| {
+                    sum += std::str::from_utf8(fields[0]).unwrap().parse::<usize>().unwrap();
+                });
+
+                sum
+            })
+        }).collect();
+
+        workers.into_iter().map(|w| w.join().unwrap()).collect()
+    });
+
+    assert!(sums.iter().sum::<usize>() == (0..10000).sum::<usize>());
+    assert!(parts[1].offset() == parts[0].len());
+
+    //The views keep the file mapped
+    drop(mapper);
+
+    let whole = parts[0].slice(0..4);
+
+    assert!(whole.get_bytes() == b"0,0\n");
+    assert!(whole.offset() == 0 && whole.len() == 4);
+
+    let (left, right) = whole.split_at(2);
+
+    assert!(left.get_bytes() == b"0," && right.get_bytes() == b"0\n");
+    assert!(right.offset() == 2);
+    assert!(MappedRange::from(parts[3].mapper().clone()).len() == data.len());
+}
+
+#[test]
+fn test_map_range() {
+    use rcsv::mmap::{next_record_start, FileMapper, MapOptions};
+
+    let dir = temp_dir("map-range");
+    let path = dir.join("data.csv");
+    let mut data = String::new();
+
+    for i in 0..20000 {
+        data.push_str(&format!("{},{}\r\n", i, "v".repeat(i % 13)));
+    }
+
+    std::fs::write(&path, &data).unwrap();
+
+    for (offset, len) in [(0, 10), (1, 5000), (4095, 2), (4096, 8193), (12345, 100000), (data.len() as u64 - 3, 100)] {
+        let mapper = FileMapper::map_range(&path, offset, len).unwrap();
+        let expected = &data.as_bytes()[offset as usize..(offset as usize + len).min(data.len())];
+
+        assert!(mapper.get_bytes() == expected);
+        assert!(mapper.offset() == offset);
+        assert!(mapper.size() == expected.len());
+
+        let snapshot = MapOptions::new().snapshot(true).map_range(&path, offset, len).unwrap();
+
+        assert!(snapshot.get_bytes() == expected);
+        assert!(snapshot.first_record_offset() == mapper.first_record_offset());
+    }
+
+    assert!(FileMapper::map_range(&path, data.len() as u64 + 10, 10).unwrap().get_bytes().is_empty());
+
+    //Each split parses the records that start in it. The mapping extends past the split to finish its last record.
+    let split = 10007;
+    let mut sum = 0;
+    let mut count = 0;
+
+    for start in (0..data.len()).step_by(split) {
+        let mapper = FileMapper::map_range(&path, start as u64, split + 100).unwrap();
+        let bytes = mapper.get_bytes();
+        let first = mapper.first_record_offset();
+        let end = next_record_start(bytes, split.min(bytes.len()));
+
+        rcsv::Parser::new().parse::<2>(&bytes[first..end], |_, fields| {
+            sum += std::str::from_utf8(fields[0]).unwrap().parse::<usize>().unwrap();
+            count += 1;
+        });
+    }
+
+    assert!(count == 20000);
+    assert!(sum == (0..20000).sum::<usize>());
+
+    //A split that starts between the CR and LF of a line end
+    let cr = data.find('\r').unwrap() as u64;
+    let mapper = FileMapper::map_range(&path, cr + 1, 20).unwrap();
+
+    assert!(mapper.first_record_offset() == 1);
+}
+
+#[test]
+fn test_mutable_file_mapper() {
+    use rcsv::mmap::{FieldLocation, FileMapper, MutableFileMapper, PatchError};
+
+    let dir = temp_dir("mutable");
+    let path = dir.join("status.csv");
+
+    std::fs::write(&path, "id,status,note\r\n1,N,\"a,b\"\r\n2,N,c\r\n3,Y,d\r\n").unwrap();
+
+    let reader = FileMapper::new(&path).unwrap();
+    let mut mapper = MutableFileMapper::new(&path).unwrap();
+    let mut statuses = Vec::new();
+    let mut notes = Vec::new();
+
+    rcsv::Parser::new().parse::<3>(mapper.get_bytes(), |index, fields| {
+        if index > 0 {
+            statuses.push(mapper.locate(fields[1]).unwrap());
+            notes.push(mapper.locate(fields[2]).unwrap());
+        }
+    });
+
+    assert!(statuses[0] == FieldLocation { offset: 18, len: 1 });
+
+    for &location in &statuses {
+        mapper.overwrite(location, b"Y").unwrap();
+    }
+
+    //Inside the quotes
+    mapper.overwrite(notes[0], b"x;y").unwrap();
+
+    assert!(mapper.overwrite(notes[1], b"long") == Err(PatchError::LengthMismatch { expected: 1, actual: 4 }));
+    assert!(mapper.overwrite(FieldLocation { offset: mapper.size(), len: 1 }, b"Y") == Err(PatchError::OutOfBounds { offset: mapper.size(), len: 1 }));
+    assert!(mapper.locate(b"Y").is_none());
+
+    mapper.flush().unwrap();
+
+    let expected = "id,status,note\r\n1,Y,\"x;y\"\r\n2,Y,c\r\n3,Y,d\r\n";
+
+    //Other mappings of the file see the change
+    assert!(reader.get_bytes() == expected.as_bytes());
+    assert!(std::fs::read(&path).unwrap() == expected.as_bytes());
+
+    let empty = dir.join("empty.csv");
+
+    std::fs::write(&empty, "").unwrap();
+
+    let empty = MutableFileMapper::new(&empty).unwrap();
+
+    assert!(empty.get_bytes().is_empty());
+
+    empty.flush().unwrap();
+}
+
+#[test]
+fn test_long_fields() {
+    //Fields of every length up to a few blocks, with the special characters at every position
+    for len in 0..150 {
+        for dialect in [rcsv::Dialect::default(), rcsv::Dialect { delimiter: b'\t', quote: b'\'', escape: Some(b'\\') }] {
+            let delimiter = dialect.delimiter as char;
+            let quote = dialect.quote as char;
+            let plain = "x".repeat(len);
+            let quoted = format!("{}{}{}", "y".repeat(len / 2), delimiter, "y".repeat(len - len / 2));
+            let quoted = format!("{quoted}\r\n{quote}{quote}{quoted}");
+            let mut escaped = "z".repeat(len);
+
+            if let Some(escape) = dialect.escape {
+                escaped.insert(len / 3, escape as char);
+                escaped.insert(len / 3 + 1, delimiter);
+            }
+
+            let data = format!("{plain}{delimiter}{quote}{quoted}{quote}{delimiter}{escaped}\r\n{plain}\n{plain}");
+            let mut parser = rcsv::Parser::with_dialect(dialect).unwrap();
+            let mut count = 0;
+
+            parser.parse::<3>(data.as_bytes(), |index, fields| {
+                if index == 0 {
+                    assert!(fields.len() == 3);
+                    assert!(fields[0] == plain.as_bytes());
+                    assert!(fields[1] == quoted.as_bytes());
+                    assert!(fields[2] == escaped.as_bytes());
+                } else {
+                    assert!(fields.len() == 1);
+                    assert!(fields[0] == plain.as_bytes());
+                }
+
+                count += 1;
+            });
+
+            assert!(count == 2);
+        }
+    }
+}
+
+#[test]
+fn test_short_fields() {
+    //A delimiter at every position of an 8 byte word, with and without a quoted field before it
+    for len in 0..24 {
+        let plain = "p".repeat(len);
+        let data = format!("{plain},\"{plain}\",{plain}\n\"\",{plain}\r\n");
+        let mut parser = rcsv::Parser::new();
+        let mut count = 0;
+
+        parser.parse::<3>(data.as_bytes(), |index, fields| {
+            if index == 0 {
+                assert!(fields.len() == 3);
+                assert!(fields.iter().all(|field| *field == plain.as_bytes()));
+            } else {
+                assert!(fields.len() == 2);
+                assert!(fields[0].is_empty());
+                assert!(fields[1] == plain.as_bytes());
+            }
+
+            count += 1;
+        });
+
+        assert!(count == 2);
+    }
+}
+
+///Generates records with quoted fields holding delimiters, quotes and line ends, and escaped characters if the
+///dialect has an escape character. One quoted field is longer than the chunks of the parallel parser.
+fn random_csv(dialect: rcsv::Dialect, records: usize) -> Vec<u8> {
+    let mut state: u64 = 12345;
+    let mut next = |n: u64| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+        (state >> 33) % n
+    };
+    let delimiter = dialect.delimiter;
+    let quote = dialect.quote;
+    let mut data = Vec::new();
+
+    for record in 0..records {
+        for field in 0..1 + next(6) {
+            if field > 0 {
+                data.push(delimiter);
+            }
+
+            match next(5) {
+                0 => {
+                    data.push(quote);
+
+                    for _ in 0..next(30) {
+                        let special = [delimiter, quote, b'\r', b'\n', b'x'][next(5) as usize];
+
+                        data.push(special);
+
+                        if special == quote {
+                            data.push(quote);
+                        }
+                    }
+
+                    if record == records / 2 {
+                        data.extend(b"long\r\n".repeat(40000));
+                    }
+
+                    data.push(quote);
+                }
+                1 if dialect.escape.is_some() => {
+                    data.extend(b"esc");
+                    data.push(dialect.escape.unwrap());
+                    data.push([delimiter, quote, b'\n', b'\r'][next(4) as usize]);
+                }
+                _ => data.extend("v".repeat(next(40) as usize).as_bytes()),
+            }
+        }
+
+        data.extend(if next(2) == 0 { &b"\r\n"[..] } else { &b"\n"[..] });
+    }
+
+    data
+}
+
+#[test]
+fn test_par_parse() {
+    use std::sync::Mutex;
+
+    let dir = temp_dir("par-parse");
+
+    for dialect in [rcsv::Dialect::default(), rcsv::Dialect { delimiter: b';', quote: b'\'', escape: Some(b'\\') }] {
+        let path = dir.join("data.csv");
+        let mut data = random_csv(dialect, 20000);
+
+        //An unterminated last record is not reported
+        data.extend(b"last,record");
+        std::fs::write(&path, &data).unwrap();
+
+        let mapper = rcsv::mmap::FileMapper::new(&path).unwrap();
+        let mut expected: Vec<Vec<Vec<u8>>> = Vec::new();
+
+        rcsv::Parser::with_dialect(dialect).unwrap().parse::<8>(mapper.get_bytes(), |_, fields| {
+            expected.push(fields.iter().map(|f| f.to_vec()).collect());
+        });
+
+        assert!(expected.len() == 20000);
+
+        for threads in [1, 3, 8] {
+            let records: Mutex<Vec<Option<Vec<Vec<u8>>>>> = Mutex::new(vec![None; expected.len()]);
+
+            rcsv::Parser::with_dialect(dialect).unwrap().par_parse::<8>(&mapper, threads, |index, fields| {
+                let mut records = records.lock().unwrap();
+
+                assert!(records[index].is_none());
+
+                records[index] = Some(fields.iter().map(|f| f.to_vec()).collect());
+            });
+
+            assert!(records.into_inner().unwrap().into_iter().zip(&expected).all(|(r, e)| r.as_ref() == Some(e)));
+
+            let mut ordered: Vec<Vec<Vec<u8>>> = Vec::new();
+
+            rcsv::Parser::with_dialect(dialect).unwrap().par_parse_ordered::<8>(&mapper, threads, |index, fields| {
+                assert!(index == ordered.len());
+
+                ordered.push(fields.iter().map(|f| f.to_vec()).collect());
+            });
+
+            assert!(ordered == expected);
+        }
+    }
+
+    let path = dir.join("empty.csv");
+
+    std::fs::write(&path, "").unwrap();
+
+    let mapper = rcsv::mmap::FileMapper::new(&path).unwrap();
+
+    rcsv::Parser::new().par_parse::<8>(&mapper, 0, |_, _| panic!("No records expected"));
+    rcsv::Parser::new().par_parse_ordered::<8>(&mapper, 0, |_, _| panic!("No records expected"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_count_records() {
+    let dir = temp_dir("count-records");
+
+    for dialect in [rcsv::Dialect::default(), rcsv::Dialect { delimiter: b';', quote: b'\'', escape: Some(b'\\') }] {
+        let path = dir.join("data.csv");
+        let data = random_csv(dialect, 5000);
+
+        std::fs::write(&path, &data).unwrap();
+
+        let parser = rcsv::Parser::with_dialect(dialect).unwrap();
+        let count = parser.count_records(&data);
+        let lines = data.iter().enumerate().filter(|&(i, &b)| b == b'\n' || (b == b'\r' && data.get(i + 1) != Some(&b'\n'))).count();
+
+        assert!(count.records == 5000);
+        assert!(count.lines == lines);
+
+        let mapper = rcsv::mmap::FileMapper::new(&path).unwrap();
+
+        for threads in [1, 4] {
+            assert!(parser.par_count_records(&mapper, threads) == count);
+        }
+    }
+
+    //The same end of data rules as parse()
+    let parser = rcsv::Parser::new();
+
+    for (data, records, lines) in [("", 0, 0), ("aa", 0, 0), ("aa\r", 1, 1), ("aa\r\r", 1, 2), ("\u{feff}aa\nbb,\"c\nc", 1, 2), ("aa,\"b\"\"\nb\"\n", 1, 2)] {
+        let count = parser.count_records(data.as_bytes());
+        let mut parsed = 0;
+
+        rcsv::Parser::new().parse::<2>(data.as_bytes(), |_, _| parsed += 1);
+
+        assert!(count.records == records && parsed == records);
+        assert!(count.lines == lines);
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_index_records() {
+    for dialect in [rcsv::Dialect::default(), rcsv::Dialect { delimiter: b';', quote: b'\'', escape: Some(b'\\') }] {
+        let mut data = random_csv(dialect, 2000);
+
+        data.extend(b"unterminated");
+
+        let mut parser = rcsv::Parser::with_dialect(dialect).unwrap();
+        let index = parser.index_records(&data);
+        let mut expected: Vec<Vec<Vec<u8>>> = Vec::new();
+
+        rcsv::Parser::with_dialect(dialect).unwrap().parse::<8>(&data, |_, fields| {
+            expected.push(fields.iter().map(|f| f.to_vec()).collect());
+        });
+
+        assert!(index.len() == expected.len());
+
+        let mut fields: [&[u8]; 8] = [&[]; 8];
+
+        for (i, expected) in expected.iter().enumerate() {
+            let record = index.record(&data, i);
+            let count = parser.split_record(record, &mut fields);
+
+            assert!(fields[0..count] == expected[..]);
+
+            //Only the first two fields
+            let count = parser.split_record(record, &mut fields[..2]);
+
+            assert!(count == expected.len().min(2));
+            assert!(fields[0..count] == expected[0..count]);
+        }
+    }
+
+    let parser = rcsv::Parser::new();
+
+    assert!(parser.index_records(b"").is_empty());
+    assert!(parser.index_records(b"aa,bb").is_empty());
+    assert!(parser.index_records("\u{feff}aa\r\nbb\n".as_bytes()).range(0) == (3..7));
+}
+
+#[test]
+fn test_find_record_start() {
+    use rcsv::mmap::Certainty;
+
+    for dialect in [rcsv::Dialect::default(), rcsv::Dialect { delimiter: b';', quote: b'\'', escape: Some(b'\\') }] {
+        let data = random_csv(dialect, 10000);
+        let parser = rcsv::Parser::with_dialect(dialect).unwrap();
+        let index = parser.index_records(&data);
+        let starts: Vec<usize> = (0..index.len()).map(|i| index.range(i).start).chain([data.len()]).collect();
+        let mut certain = 0;
+
+        for offset in (0..data.len()).step_by(4999) {
+            let start = parser.find_record_start(&data, offset);
+            let expected = starts[starts.partition_point(|&s| s < offset)];
+
+            if start.certainty == Certainty::Certain {
+                assert!(start.offset == expected);
+
+                certain += 1;
+            }
+        }
+
+        //Quoted fields are common enough to tell the quoting apart
+        assert!(certain > data.len() / 4999 / 2);
+    }
+
+    //Inside a quoted field longer than the look back and the look ahead, both guesses are well formed
+    let mut data = b"id,text\n1,\"".to_vec();
+
+    data.extend(b"line\n".repeat(100000));
+    data.extend(b"\"\n2,b\n");
+
+    let start = rcsv::mmap::find_record_start(&data, 250000);
+
+    assert!(start.certainty == Certainty::Probable);
+    assert!(rcsv::mmap::find_record_start(&data, 20).offset == data.len() - 4);
+}
+
+#[test]
+fn test_parse_batches() {
+    let data = random_csv(rcsv::Dialect::default(), 1000);
+    let mut expected: Vec<Vec<Vec<u8>>> = Vec::new();
+
+    rcsv::Parser::new().parse::<4>(&data, |_, fields| {
+        expected.push(fields.iter().map(|f| f.to_vec()).collect());
+    });
+
+    for batch_size in [1, 7, 1000, 5000, usize::MAX] {
+        let mut records: Vec<Vec<Vec<u8>>> = Vec::new();
+        let mut batches = 0;
+
+        rcsv::Parser::new().parse_batches::<4>(&data, batch_size, |batch| {
+            assert!(batch.first_index() == records.len());
+            assert!(batch.len() == batch_size.min(1000 - records.len()));
+
+            for (i, first) in batch.column(0).enumerate() {
+                assert!(first == batch.field(i, 0).unwrap());
+                assert!(batch.field(i, batch.field_count(i)).is_none());
+
+                records.push((0..batch.field_count(i)).map(|j| batch.field(i, j).unwrap().to_vec()).collect());
+            }
+
+            batches += 1;
+        });
+
+        assert!(records == expected);
+        assert!(batches == 1000usize.div_ceil(batch_size));
+    }
+
+    //A batch grows past the records it sets memory aside for
+    let lines: String = (0..3000).map(|i| format!("{},x\n", i)).collect();
+    let mut batches = 0;
+
+    rcsv::Parser::new().parse_batches::<2>(lines.as_bytes(), usize::MAX, |batch| {
+        assert!(batch.len() == 3000);
+        assert!(batch.column(0).enumerate().all(|(i, field)| field == i.to_string().as_bytes()));
+
+        batches += 1;
+    });
+
+    assert!(batches == 1);
+
+    rcsv::Parser::new().parse_batches::<4>(b"", 10, |_| panic!("No batches expected"));
+}
+
+#[test]
+fn test_parse_integers() {
+    use rcsv::number::{parse_i32, parse_i64, parse_u64, NumberErrorKind};
+
+    assert!(parse_i64(b"0") == Ok(0));
+    assert!(parse_i64(b"+17") == Ok(17));
+    assert!(parse_i64(b"-9223372036854775808") == Ok(i64::MIN));
+    assert!(parse_i64(b"9223372036854775807") == Ok(i64::MAX));
+    assert!(parse_i32(b"-2147483648") == Ok(i32::MIN));
+    assert!(parse_u64(b"18446744073709551615") == Ok(u64::MAX));
+
+    let error = parse_u64(b"18446744073709551616").unwrap_err();
+
+    assert!(error.kind() == NumberErrorKind::Overflow && error.offset() == 19);
+
+    let error = parse_i32(b"2147483648").unwrap_err();
+
+    assert!(error.kind() == NumberErrorKind::Overflow && error.offset() == 9);
+
+    for (field, kind, offset) in [("", NumberErrorKind::Empty, 0), ("-", NumberErrorKind::Empty, 1), (" 1", NumberErrorKind::InvalidDigit, 0), ("12a", NumberErrorKind::InvalidDigit, 2), ("1.5", NumberErrorKind::InvalidDigit, 1)] {
+        let error = parse_i64(field.as_bytes()).unwrap_err();
+
+        assert!(error.kind() == kind && error.offset() == offset);
+    }
+
+    assert!(parse_u64(b"-1").unwrap_err().offset() == 0);
+    //Not valid UTF-8
+    assert!(parse_i64(&[b'1', 0xff]).unwrap_err().offset() == 1);
+}
+
+#[test]
+fn test_parse_floats() {
+    use rcsv::number::{parse_f32, parse_f64, NumberErrorKind};
+
+    fn check(field: &str) {
+        let expected = field.parse::<f64>().unwrap();
+        let value = parse_f64(field.as_bytes()).unwrap();
+
+        assert!(value.to_bits() == expected.to_bits() || (value.is_nan() && expected.is_nan()), "{}", field);
+
+        let expected = field.parse::<f32>().unwrap();
+        let value = parse_f32(field.as_bytes()).unwrap();
+
+        assert!(value.to_bits() == expected.to_bits() || (value.is_nan() && expected.is_nan()), "{}", field);
+    }
+
+    for field in [
+        "0", "-0", "+0.0", "1", "1.", ".5", "-.5e-3", "1e23", "9007199254740993", "9007199254740992.5",
+        "2.2250738585072011e-308", "2.2250738585072014e-308", "4.9406564584124654e-324", "2.4703282292062327e-324",
+        "2.4703282292062328e-324", "1.7976931348623157e308", "1.7976931348623159e308", "1e309", "1e-400",
+        "123456789012345678901234567890", "0.000000000000000000000000000001234567890123456789012345",
+        "3.4028235e38", "3.4028236e38", "1.4e-45", "7.0e-46", "16777217", "0.1", "0.2", "0.3", "1e0", "1E+2",
+        "inf", "-Infinity", "NaN", "1e99999999999999", "0e99999999999999", "00000000000000000000001.5",
+        "1.00000000000000011102230246251565404236316680908203125", "1.00000000000000011102230246251565404236316680908203124",
+        "1.00000000000000011102230246251565404236316680908203126",
+    ] {
+        check(field);
+    }
+
+    let mut state: u64 = 42;
+
+    for _ in 0..20000 {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+
+        let bits = state;
+        let double = f64::from_bits(bits);
+
+        if double.is_finite() {
+            check(&format!("{}", double));
+            check(&format!("{:e}", double));
+            check(&format!("{:e}", f32::from_bits(bits as u32)));
+        }
+
+        //Random digits and exponents
+        let digits = format!("{}", state >> ((state & 31) + 1));
+        let dot = (state >> 40) as usize % (digits.len() + 1);
+        let exponent = (state >> 48) as i64 % 700 - 350;
+
+        check(&format!("{}.{}e{}", &digits[..dot], &digits[dot..], exponent));
+    }
+
+    for (field, kind, offset) in [("", NumberErrorKind::Empty, 0), (".", NumberErrorKind::Empty, 1), ("1e", NumberErrorKind::Empty, 2), ("1e+", NumberErrorKind::Empty, 3), ("1,5", NumberErrorKind::InvalidDigit, 1), ("1.5 ", NumberErrorKind::InvalidDigit, 3), ("e5", NumberErrorKind::InvalidDigit, 0), ("infinit", NumberErrorKind::InvalidDigit, 0)] {
+        let error = parse_f64(field.as_bytes()).unwrap_err();
+
+        assert!(error.kind() == kind && error.offset() == offset, "{}", field);
+        assert!(field.parse::<f64>().is_err());
+    }
+}